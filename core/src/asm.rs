@@ -0,0 +1,593 @@
+//! Assembler for CHIP-8, SUPER-CHIP and XO-CHIP programs
+//!
+//! Accepts the Cowgod-style mnemonics documented in `opcodes.rs` and
+//! printed by the disassembler, e.g. `LD V0, 0x0A` or `DRW V0, V1, 5`,
+//! together with:
+//!
+//! * labels, written either as `name:` or Octo-style as `: name`
+//! * constants, `define NAME value` or `:const NAME value`
+//! * data, `DB` / `BYTE` for bytes and `DW` / `WORD` for big-endian words
+//! * `include "file"`, resolved relative to the including file
+//! * comments starting with `;` or `#`
+//!
+//! Numbers are decimal, `0x` hex or `0b` binary. Operands may add or
+//! subtract numbers, labels and constants, e.g. `LD I, sprites + 5`.
+//! The output is a binary to be loaded at `PROGRAM_START`.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::vm::{PROGRAM_START, RAM_SIZE};
+
+/// Maximum depth of nested include files
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// Error found while assembling, with its position in the source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AsmError {
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}:{}: {}", self.file, self.line, self.column, self.message)
+    }
+}
+
+impl Error for AsmError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Kind {
+    Word,
+    Str,
+    Comma,
+    Colon,
+    Plus,
+    Minus,
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: Kind,
+    text: String,
+    column: usize,
+}
+
+/// Position of a line in the sources
+#[derive(Debug, Clone)]
+struct Location {
+    file: String,
+    line: usize,
+}
+
+impl Location {
+    fn error(&self, column: usize, message: String) -> AsmError {
+        AsmError {
+            file: self.file.clone(),
+            line: self.line,
+            column,
+            message,
+        }
+    }
+}
+
+/// Instruction or data directive waiting for its operands to be resolved
+#[derive(Debug, Clone)]
+struct Statement {
+    location: Location,
+    mnemonic: Token,
+    operands: Vec<Vec<Token>>,
+    offset: usize,
+}
+
+#[derive(Debug, Clone)]
+enum Symbol {
+    Label(usize),
+    Constant(Location, Vec<Token>),
+}
+
+/// Operand of an instruction
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Operand {
+    Register(u8),
+    /// `I`, `[I]`, `DT`, `ST`, `K`, `F`, `HF`, `B` or `R`
+    Keyword(&'static str),
+    /// `LONG expr`
+    Long(i64),
+    Value(i64),
+}
+
+const KEYWORDS: [&str; 9] = ["I", "[I]", "DT", "ST", "K", "F", "HF", "B", "R"];
+
+fn tokenize(text: &str, location: &Location) -> Result<Vec<Token>, AsmError> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut index = 0;
+
+    while index < chars.len() {
+        let c = chars[index];
+        let column = index + 1;
+        let single = |kind: Kind| Token { kind, text: c.to_string(), column };
+
+        match c {
+            ';' | '#' => break,
+            ' ' | '\t' | '\r' => index += 1,
+            ',' => { tokens.push(single(Kind::Comma)); index += 1; }
+            ':' => { tokens.push(single(Kind::Colon)); index += 1; }
+            '+' => { tokens.push(single(Kind::Plus)); index += 1; }
+            '-' => { tokens.push(single(Kind::Minus)); index += 1; }
+            '"' => {
+                let end = chars[index + 1..].iter().position(|&c| c == '"')
+                    .ok_or_else(|| location.error(column, "unterminated string".to_string()))?;
+                let text: String = chars[index + 1..index + 1 + end].iter().collect();
+                tokens.push(Token { kind: Kind::Str, text, column });
+                index += end + 2;
+            }
+            _ if c.is_alphanumeric() || "_.$[]".contains(c) => {
+                let start = index;
+                while index < chars.len() && (chars[index].is_alphanumeric() || "_.$[]".contains(chars[index])) {
+                    index += 1;
+                }
+                tokens.push(Token { kind: Kind::Word, text: chars[start..index].iter().collect(), column });
+            }
+            _ => return Err(location.error(column, format!("unexpected character '{}'", c))),
+        }
+    }
+    Ok(tokens)
+}
+
+fn parse_number(text: &str) -> Option<i64> {
+    let lower = text.to_ascii_lowercase();
+    if let Some(hex) = lower.strip_prefix("0x") {
+        i64::from_str_radix(hex, 16).ok()
+    } else if let Some(binary) = lower.strip_prefix("0b") {
+        i64::from_str_radix(binary, 2).ok()
+    } else if lower.starts_with(|c: char| c.is_ascii_digit()) {
+        lower.parse().ok()
+    } else {
+        None
+    }
+}
+
+fn is_identifier(text: &str) -> bool {
+    text.starts_with(|c: char| c.is_alphabetic() || c == '_')
+        && text.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '.')
+}
+
+/// Size in bytes of a statement, known before its operands are resolved
+fn statement_size(mnemonic: &str, operands: &[Vec<Token>]) -> usize {
+    match mnemonic {
+        "DB" | "BYTE" => operands.len(),
+        "DW" | "WORD" => operands.len() * 2,
+        "LD" => {
+            let long = operands.get(1)
+                .and_then(|operand| operand.first())
+                .is_some_and(|token| token.text.eq_ignore_ascii_case("LONG"));
+            if long { 4 } else { 2 }
+        }
+        _ => 2,
+    }
+}
+
+/// Collects the statements and symbols of a program
+struct Assembler {
+    statements: Vec<Statement>,
+    symbols: HashMap<String, Symbol>,
+    size: usize,
+}
+
+impl Assembler {
+    fn new() -> Assembler {
+        Assembler {
+            statements: Vec::new(),
+            symbols: HashMap::new(),
+            size: 0,
+        }
+    }
+
+    fn define(&mut self, location: &Location, name: &Token, symbol: Symbol) -> Result<(), AsmError> {
+        if !is_identifier(&name.text) || is_reserved(&name.text) {
+            return Err(location.error(name.column, format!("invalid name '{}'", name.text)));
+        }
+        if self.symbols.contains_key(&name.text) {
+            return Err(location.error(name.column, format!("'{}' is already defined", name.text)));
+        }
+        self.symbols.insert(name.text.clone(), symbol);
+        Ok(())
+    }
+
+    /// First pass over one source file: records labels, constants and
+    /// statements, following include files
+    fn read(&mut self, source: &str, file: &str, dir: &Path, depth: usize) -> Result<(), AsmError> {
+        for (index, text) in source.lines().enumerate() {
+            let location = Location { file: file.to_string(), line: index + 1 };
+            let mut tokens = tokenize(text, &location)?;
+
+            // labels, `name:` or `: name`, possibly followed by a statement
+            loop {
+                let is_label = match tokens.as_slice() {
+                    [name, colon, ..] => name.kind == Kind::Word && colon.kind == Kind::Colon,
+                    _ => false,
+                };
+                let is_octo_label = match tokens.as_slice() {
+                    [colon, name, ..] => {
+                        colon.kind == Kind::Colon && name.kind == Kind::Word && name.text != "const"
+                    }
+                    _ => false,
+                };
+                if !is_label && !is_octo_label {
+                    break;
+                }
+                let name = if is_label { tokens.remove(0) } else { tokens.remove(1) };
+                tokens.remove(0);
+                let address = PROGRAM_START + self.size;
+                self.define(&location, &name, Symbol::Label(address))?;
+            }
+
+            let first = match tokens.first() {
+                Some(first) => first.clone(),
+                None => continue,
+            };
+
+            // constants, `define NAME value` or `:const NAME value`
+            let octo_const = first.kind == Kind::Colon
+                && tokens.get(1).is_some_and(|token| token.text == "const");
+            if octo_const || first.text.eq_ignore_ascii_case("define") {
+                let skip = if octo_const { 2 } else { 1 };
+                let name = tokens.get(skip).cloned()
+                    .ok_or_else(|| location.error(first.column, "missing constant name".to_string()))?;
+                let value = tokens[skip + 1..].to_vec();
+                if value.is_empty() {
+                    return Err(location.error(name.column, format!("missing value of '{}'", name.text)));
+                }
+                self.define(&location, &name, Symbol::Constant(location.clone(), value))?;
+                continue;
+            }
+
+            if first.text.eq_ignore_ascii_case("include") {
+                let path = match tokens.as_slice() {
+                    [_, path] if path.kind == Kind::Str => path,
+                    _ => return Err(location.error(first.column, "expected include \"file\"".to_string())),
+                };
+                if depth >= MAX_INCLUDE_DEPTH {
+                    return Err(location.error(path.column, "includes are nested too deeply".to_string()));
+                }
+                let included = dir.join(&path.text);
+                let source = fs::read_to_string(&included).map_err(|why| {
+                    location.error(path.column, format!("couldn't read {}: {}", included.display(), why))
+                })?;
+                let included_dir = included.parent().map(Path::to_path_buf).unwrap_or_default();
+                self.read(&source, &included.display().to_string(), &included_dir, depth + 1)?;
+                continue;
+            }
+
+            if first.kind != Kind::Word {
+                return Err(location.error(first.column, format!("unexpected '{}'", first.text)));
+            }
+            let mut operands: Vec<Vec<Token>> = Vec::new();
+            let mut current = Vec::new();
+            let end = text.chars().count() + 1;
+            for token in tokens.into_iter().skip(1) {
+                if token.kind == Kind::Comma {
+                    if current.is_empty() {
+                        return Err(location.error(token.column, "missing operand".to_string()));
+                    }
+                    operands.push(current);
+                    current = Vec::new();
+                } else {
+                    current.push(token);
+                }
+            }
+            if !current.is_empty() {
+                operands.push(current);
+            } else if !operands.is_empty() {
+                return Err(location.error(end, "missing operand".to_string()));
+            }
+
+            let mut mnemonic = first;
+            mnemonic.text = mnemonic.text.to_ascii_uppercase();
+            let size = statement_size(&mnemonic.text, &operands);
+            self.statements.push(Statement { location, mnemonic, operands, offset: self.size });
+            self.size += size;
+        }
+        Ok(())
+    }
+
+    /// Evaluates an expression of numbers, labels and constants
+    fn evaluate(&self, tokens: &[Token], location: &Location, depth: usize) -> Result<i64, AsmError> {
+        let mut value = 0i64;
+        let mut sign = Some(1);
+
+        for token in tokens {
+            match (token.kind.clone(), sign) {
+                (Kind::Plus, None) => sign = Some(1),
+                (Kind::Minus, None) => sign = Some(-1),
+                (Kind::Minus, Some(current)) => sign = Some(-current),
+                (Kind::Word, Some(current)) => {
+                    value += current * self.resolve(token, location, depth)?;
+                    sign = None;
+                }
+                _ => return Err(location.error(token.column, format!("unexpected '{}'", token.text))),
+            }
+        }
+        if sign.is_some() {
+            let column = tokens.last().map_or(1, |token| token.column + 1);
+            return Err(location.error(column, "missing value".to_string()));
+        }
+        Ok(value)
+    }
+
+    fn resolve(&self, token: &Token, location: &Location, depth: usize) -> Result<i64, AsmError> {
+        if let Some(number) = parse_number(&token.text) {
+            return Ok(number);
+        }
+        match self.symbols.get(&token.text) {
+            Some(Symbol::Label(address)) => Ok(*address as i64),
+            Some(Symbol::Constant(_, _)) if depth > self.symbols.len() => {
+                Err(location.error(token.column, format!("'{}' is defined in terms of itself", token.text)))
+            }
+            Some(Symbol::Constant(defined, tokens)) => self.evaluate(tokens, defined, depth + 1),
+            None => Err(location.error(token.column, format!("unknown name '{}'", token.text))),
+        }
+    }
+
+    fn operand(&self, tokens: &[Token], location: &Location) -> Result<Operand, AsmError> {
+        if let [token] = tokens {
+            let upper = token.text.to_ascii_uppercase();
+            if let Some(register) = register(&upper) {
+                return Ok(Operand::Register(register));
+            }
+            if let Some(keyword) = KEYWORDS.iter().find(|&&keyword| keyword == upper) {
+                return Ok(Operand::Keyword(keyword));
+            }
+        }
+        if tokens[0].text.eq_ignore_ascii_case("LONG") && tokens.len() > 1 {
+            return Ok(Operand::Long(self.evaluate(&tokens[1..], location, 0)?));
+        }
+        Ok(Operand::Value(self.evaluate(tokens, location, 0)?))
+    }
+
+    /// Second pass: encodes all statements
+    fn encode(&self) -> Result<Vec<u8>, AsmError> {
+        let capacity = RAM_SIZE - PROGRAM_START;
+        let overflowing = self.statements.iter()
+            .find(|statement| statement.offset + statement_size(&statement.mnemonic.text, &statement.operands) > capacity);
+        if let Some(statement) = overflowing {
+            return Err(statement.location.error(statement.mnemonic.column, format!(
+                "program is {} bytes, at most {} bytes fit into RAM", self.size, capacity)));
+        }
+
+        let mut output = Vec::with_capacity(self.size);
+
+        for statement in &self.statements {
+            let location = &statement.location;
+            let mnemonic = &statement.mnemonic;
+            let operands = statement.operands.iter()
+                .map(|tokens| Ok((self.operand(tokens, location)?, tokens[0].column)))
+                .collect::<Result<Vec<_>, AsmError>>()?;
+
+            match mnemonic.text.as_str() {
+                "DB" | "BYTE" | "DW" | "WORD" => {
+                    let word = mnemonic.text == "DW" || mnemonic.text == "WORD";
+                    if operands.is_empty() {
+                        return Err(location.error(mnemonic.column, "missing data".to_string()));
+                    }
+                    for (operand, column) in operands {
+                        let value = match operand {
+                            Operand::Value(value) => value,
+                            _ => return Err(location.error(column, "expected a number".to_string())),
+                        };
+                        if word {
+                            let value = check(value, 0xFFFF, "word", location, column)?;
+                            output.extend_from_slice(&[(value >> 8) as u8, value as u8]);
+                        } else {
+                            output.push(check(value, 0xFF, "byte", location, column)? as u8);
+                        }
+                    }
+                }
+                _ => {
+                    let words = encode_instruction(mnemonic, &operands, location)?;
+                    for word in words {
+                        output.extend_from_slice(&[(word >> 8) as u8, word as u8]);
+                    }
+                }
+            }
+            debug_assert_eq!(output.len(), statement.offset + statement_size(&mnemonic.text, &statement.operands));
+        }
+        Ok(output)
+    }
+}
+
+fn register(text: &str) -> Option<u8> {
+    let digit = text.strip_prefix('V')?;
+    if digit.len() != 1 {
+        return None;
+    }
+    u8::from_str_radix(digit, 16).ok()
+}
+
+fn is_reserved(text: &str) -> bool {
+    let upper = text.to_ascii_uppercase();
+    register(&upper).is_some() || KEYWORDS.contains(&upper.as_str()) || upper == "LONG"
+}
+
+/// Checks that `value` fits into `max`, accepting negative bytes as two's complement
+fn check(value: i64, max: i64, what: &str, location: &Location, column: usize) -> Result<u16, AsmError> {
+    let value = if max == 0xFF && (-128..0).contains(&value) { value + 0x100 } else { value };
+    if value < 0 || value > max {
+        return Err(location.error(column, format!("{} out of range: {}", what, value)));
+    }
+    Ok(value as u16)
+}
+
+fn encode_instruction(mnemonic: &Token, operands: &[(Operand, usize)], location: &Location) -> Result<Vec<u16>, AsmError> {
+    use self::Operand::*;
+
+    let addr = |value: i64, column: usize| check(value, 0xFFF, "address", location, column);
+    let byte = |value: i64, column: usize| check(value, 0xFF, "byte", location, column);
+    let nibble = |value: i64, column: usize| check(value, 0xF, "nibble", location, column);
+    let xy = |x: u8, y: u8| ((x as u16) << 8) | ((y as u16) << 4);
+    let x = |x: u8| (x as u16) << 8;
+
+    let kinds: Vec<&Operand> = operands.iter().map(|(operand, _)| operand).collect();
+    let column = |index: usize| operands.get(index).map_or(mnemonic.column, |&(_, column)| column);
+
+    let opcode = match (mnemonic.text.as_str(), kinds.as_slice()) {
+        ("CLS", []) => 0x00E0,
+        ("RET", []) => 0x00EE,
+        ("SCR", []) => 0x00FB,
+        ("SCL", []) => 0x00FC,
+        ("EXIT", []) => 0x00FD,
+        ("LOW", []) => 0x00FE,
+        ("HIGH", []) => 0x00FF,
+        ("AUDIO", []) => 0xF002,
+        ("SCD", [Value(n)]) => 0x00C0 | nibble(*n, column(0))?,
+        ("SCU", [Value(n)]) => 0x00D0 | nibble(*n, column(0))?,
+        ("PLANE", [Value(n)]) => 0xF001 | check(*n, 3, "plane mask", location, column(0))? << 8,
+        ("JP", [Value(target)]) => 0x1000 | addr(*target, column(0))?,
+        ("JP", [Register(0), Value(target)]) => 0xB000 | addr(*target, column(1))?,
+        ("CALL", [Value(target)]) => 0x2000 | addr(*target, column(0))?,
+        ("SE", [Register(vx), Value(value)]) => 0x3000 | x(*vx) | byte(*value, column(1))?,
+        ("SNE", [Register(vx), Value(value)]) => 0x4000 | x(*vx) | byte(*value, column(1))?,
+        ("SE", [Register(vx), Register(vy)]) => 0x5000 | xy(*vx, *vy),
+        ("SAVE", [Register(vx), Register(vy)]) => 0x5002 | xy(*vx, *vy),
+        ("LOAD", [Register(vx), Register(vy)]) => 0x5003 | xy(*vx, *vy),
+        ("LD", [Register(vx), Value(value)]) => 0x6000 | x(*vx) | byte(*value, column(1))?,
+        ("ADD", [Register(vx), Value(value)]) => 0x7000 | x(*vx) | byte(*value, column(1))?,
+        ("LD", [Register(vx), Register(vy)]) => 0x8000 | xy(*vx, *vy),
+        ("OR", [Register(vx), Register(vy)]) => 0x8001 | xy(*vx, *vy),
+        ("AND", [Register(vx), Register(vy)]) => 0x8002 | xy(*vx, *vy),
+        ("XOR", [Register(vx), Register(vy)]) => 0x8003 | xy(*vx, *vy),
+        ("ADD", [Register(vx), Register(vy)]) => 0x8004 | xy(*vx, *vy),
+        ("SUB", [Register(vx), Register(vy)]) => 0x8005 | xy(*vx, *vy),
+        ("SHR", [Register(vx)]) => 0x8006 | xy(*vx, *vx),
+        ("SHR", [Register(vx), Register(vy)]) => 0x8006 | xy(*vx, *vy),
+        ("SUBN", [Register(vx), Register(vy)]) => 0x8007 | xy(*vx, *vy),
+        ("SHL", [Register(vx)]) => 0x800E | xy(*vx, *vx),
+        ("SHL", [Register(vx), Register(vy)]) => 0x800E | xy(*vx, *vy),
+        ("SNE", [Register(vx), Register(vy)]) => 0x9000 | xy(*vx, *vy),
+        ("LD", [Keyword("I"), Value(target)]) => 0xA000 | addr(*target, column(1))?,
+        ("LD", [Keyword("I"), Long(target)]) => {
+            let target = check(*target, 0xFFFF, "address", location, column(1))?;
+            return Ok(vec![0xF000, target]);
+        }
+        ("RND", [Register(vx), Value(value)]) => 0xC000 | x(*vx) | byte(*value, column(1))?,
+        ("DRW", [Register(vx), Register(vy), Value(n)]) => 0xD000 | xy(*vx, *vy) | nibble(*n, column(2))?,
+        ("SKP", [Register(vx)]) => 0xE09E | x(*vx),
+        ("SKNP", [Register(vx)]) => 0xE0A1 | x(*vx),
+        ("LD", [Register(vx), Keyword("DT")]) => 0xF007 | x(*vx),
+        ("LD", [Register(vx), Keyword("K")]) => 0xF00A | x(*vx),
+        ("LD", [Keyword("DT"), Register(vx)]) => 0xF015 | x(*vx),
+        ("LD", [Keyword("ST"), Register(vx)]) => 0xF018 | x(*vx),
+        ("ADD", [Keyword("I"), Register(vx)]) => 0xF01E | x(*vx),
+        ("LD", [Keyword("F"), Register(vx)]) => 0xF029 | x(*vx),
+        ("LD", [Keyword("HF"), Register(vx)]) => 0xF030 | x(*vx),
+        ("LD", [Keyword("B"), Register(vx)]) => 0xF033 | x(*vx),
+        ("PITCH", [Register(vx)]) => 0xF03A | x(*vx),
+        ("LD", [Keyword("[I]"), Register(vx)]) => 0xF055 | x(*vx),
+        ("LD", [Register(vx), Keyword("[I]")]) => 0xF065 | x(*vx),
+        ("LD", [Keyword("R"), Register(vx)]) => 0xF075 | x(*vx),
+        ("LD", [Register(vx), Keyword("R")]) => 0xF085 | x(*vx),
+        (name, _) if is_mnemonic(name) => {
+            return Err(location.error(mnemonic.column, format!("invalid operands for {}", name)));
+        }
+        (name, _) => return Err(location.error(mnemonic.column, format!("unknown instruction '{}'", name))),
+    };
+    Ok(vec![opcode])
+}
+
+fn is_mnemonic(name: &str) -> bool {
+    const MNEMONICS: [&str; 31] = [
+        "CLS", "RET", "SCR", "SCL", "EXIT", "LOW", "HIGH", "AUDIO", "SCD", "SCU", "PLANE", "JP", "CALL", "SE",
+        "SNE", "SAVE", "LOAD", "LD", "ADD", "OR", "AND", "XOR", "SUB", "SHR", "SUBN", "SHL", "RND", "DRW",
+        "SKP", "SKNP", "PITCH",
+    ];
+    MNEMONICS.contains(&name)
+}
+
+/// Assembles `source`; include files are resolved relative to the current directory
+pub fn assemble(source: &str) -> Result<Vec<u8>, AsmError> {
+    let mut assembler = Assembler::new();
+    assembler.read(source, "<source>", Path::new(""), 0)?;
+    assembler.encode()
+}
+
+/// Assembles the file at `path`
+pub fn assemble_file<P: AsRef<Path>>(path: P) -> Result<Vec<u8>, AsmError> {
+    let path = path.as_ref();
+    let source = fs::read_to_string(path).map_err(|why| AsmError {
+        file: path.display().to_string(),
+        line: 0,
+        column: 0,
+        message: format!("couldn't read file: {}", why),
+    })?;
+
+    let dir = path.parent().map(Path::to_path_buf).unwrap_or_else(PathBuf::new);
+    let mut assembler = Assembler::new();
+    assembler.read(&source, &path.display().to_string(), &dir, 0)?;
+    assembler.encode()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Assembles `source`, expecting an error, and returns its message with
+    /// its location
+    fn error(source: &str) -> String {
+        assemble(source).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn encodes_instructions_and_labels() {
+        let source = "
+            define SPEED 3
+    start:  LD V0, SPEED
+            ADD V0, 0xFF
+            LD I, sprite
+            DRW V0, V1, 2
+            PLANE 3
+            JP start
+    sprite: DB 0xF0, 0x90
+        ";
+
+        assert_eq!(assemble(source).unwrap(), [
+            0x60, 0x03,
+            0x70, 0xFF,
+            0xA2, 0x0C,
+            0xD0, 0x12,
+            0xF3, 0x01,
+            0x12, 0x00,
+            0xF0, 0x90,
+        ]);
+    }
+
+    #[test]
+    fn rejects_planes_above_3() {
+        assert_eq!(assemble("PLANE 0").unwrap(), [0xF0, 0x01]);
+        assert_eq!(error("CLS\n  PLANE 4"), "<source>:2:9: plane mask out of range: 4");
+        assert_eq!(error("PLANE 0xF"), "<source>:1:7: plane mask out of range: 15");
+    }
+
+    #[test]
+    fn reports_the_location_of_errors() {
+        assert_eq!(error("LD V0, 0x100"), "<source>:1:8: byte out of range: 256");
+        assert_eq!(error("\nJP nowhere"), "<source>:2:4: unknown name 'nowhere'");
+        assert_eq!(error("FOO"), "<source>:1:1: unknown instruction 'FOO'");
+        assert_eq!(error("CLS\n  SKP 5"), "<source>:2:3: invalid operands for SKP");
+    }
+
+    #[test]
+    fn reports_the_statement_that_overflows_ram() {
+        let source = format!("DB {}\nDW 1, 2\nCLS", vec!["0"; RAM_SIZE - PROGRAM_START - 3].join(", "));
+        let capacity = RAM_SIZE - PROGRAM_START;
+        assert_eq!(error(&source), format!("<source>:2:1: program is {} bytes, at most {} bytes fit into RAM",
+                                           capacity + 3, capacity));
+    }
+}
@@ -0,0 +1,86 @@
+/// Size of the XO-CHIP audio pattern buffer in bytes
+pub const PATTERN_BYTES: usize = 16;
+/// Number of 1-bit samples in the audio pattern buffer
+pub const PATTERN_BITS: usize = PATTERN_BYTES * 8;
+/// Value of the pitch register at power-on, playing the pattern at 4000 Hz
+pub const DEFAULT_PITCH: u8 = 64;
+/// Frequency of the classic buzzer in Hz
+pub const BUZZER_FREQUENCY: f32 = 440.0;
+
+/// Sound played while the sound timer is running
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sound {
+    /// 1-bit pattern loaded by `F002`, `None` until the ROM loads one
+    pub pattern: Option<[u8; PATTERN_BYTES]>,
+
+    /// Pitch register set by `FX3A`
+    pub pitch: u8,
+}
+
+impl Sound {
+    /// Playback rate of the pattern in bits per second
+    pub fn playback_rate(&self) -> f32 {
+        4000.0 * 2f32.powf((self.pitch as f32 - 64.0) / 48.0)
+    }
+}
+
+impl Default for Sound {
+    fn default() -> Sound {
+        Sound {
+            pattern: None,
+            pitch: DEFAULT_PITCH,
+        }
+    }
+}
+
+/// Generator of the samples of a `Sound`
+///
+/// Plays the pattern buffer when the ROM loaded one, otherwise falls back
+/// to a square wave at `BUZZER_FREQUENCY`.
+pub struct Tone {
+    sound: Sound,
+    sample_rate: f32,
+    volume: f32,
+    /// Position in the current period, from 0 to 1
+    phase: f32,
+}
+
+impl Tone {
+    /// Creates a generator producing `sample_rate` samples per second
+    pub fn new(sample_rate: i32, volume: f32) -> Tone {
+        Tone {
+            sound: Sound::default(),
+            sample_rate: sample_rate as f32,
+            volume,
+            phase: 0.0,
+        }
+    }
+
+    /// Changes the sound being played, keeping the phase
+    pub fn set_sound(&mut self, sound: Sound) {
+        self.sound = sound;
+    }
+
+    /// Fills `out` with the next samples
+    pub fn fill(&mut self, out: &mut [f32]) {
+        match self.sound.pattern {
+            Some(pattern) => {
+                let phase_inc = self.sound.playback_rate() / PATTERN_BITS as f32 / self.sample_rate;
+                for x in out.iter_mut() {
+                    let bit = (self.phase * PATTERN_BITS as f32) as usize % PATTERN_BITS;
+                    let high = pattern[bit / 8] & (0x80 >> (bit % 8)) != 0;
+                    *x = if high { self.volume } else { -self.volume };
+                    self.phase = (self.phase + phase_inc) % 1.0;
+                }
+            }
+            None => {
+                // Generate a square wave
+                let phase_inc = BUZZER_FREQUENCY / self.sample_rate;
+                for x in out.iter_mut() {
+                    *x = if self.phase <= 0.5 { self.volume } else { -self.volume };
+                    self.phase = (self.phase + phase_inc) % 1.0;
+                }
+            }
+        }
+    }
+}
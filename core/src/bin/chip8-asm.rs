@@ -0,0 +1,46 @@
+//! Assembles a source file into a ROM
+
+extern crate chip8_core;
+
+use std::fs;
+use std::path::Path;
+use std::process;
+
+use chip8_core::asm;
+use chip8_core::cli::{self, Args};
+
+const USAGE: &str = "usage: chip8-asm <source> [-o <path-to-ROM>]
+
+Writes the ROM next to the source with a .ch8 extension unless -o is given.";
+
+fn main() {
+    let mut source = None;
+    let mut output = None;
+
+    let mut args = Args::new("chip8-asm", USAGE);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" | "--output" => output = Some(args.value(&arg)),
+            _ if cli::is_option(&arg) => args.unexpected(&arg),
+            _ if source.is_none() => source = Some(arg),
+            _ => args.unexpected(&arg),
+        }
+    }
+    let source = source.unwrap_or_else(|| args.usage("missing source"));
+    let output = output.unwrap_or_else(|| {
+        let path = Path::new(&source).with_extension("ch8");
+        path.display().to_string()
+    });
+
+    let rom = match asm::assemble_file(&source) {
+        Ok(rom) => rom,
+        Err(why) => {
+            eprintln!("{}", why);
+            process::exit(1);
+        }
+    };
+    if let Err(why) = fs::write(&output, &rom) {
+        eprintln!("couldn't write {}: {}", output, why);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,151 @@
+//! Replays a ROM against the trace of a reference emulator
+//!
+//! The reference trace uses the format written by `--trace`. The ROM runs
+//! until the cycle of every reference line and the machine state is
+//! compared; the first difference is reported along with the
+//! instructions around it.
+
+extern crate chip8_core;
+
+use std::collections::VecDeque;
+use std::fs;
+use std::process;
+
+use chip8_core::cli::{self, Args};
+use chip8_core::headless::{self, KeyScript, Limit, RunStatus};
+use chip8_core::trace::TraceLine;
+use chip8_core::vm::CYCLES_PER_FRAME;
+use chip8_core::{NullFrontend, Quirks, Vm};
+
+const USAGE: &str = "usage: chip8-difftest <path-to-ROM> <reference-trace> [options]
+
+options:
+  --keys <file>          scripted key input, lines of '<frame> <down|up> <key>'
+  --quirks <profile>     quirks profile
+  --cycles-per-frame <n> instructions per 60 Hz frame (default 10)
+  --seed <n>             seed of the random number generator
+  --context <n>          instructions shown around the divergence (default 5)
+
+Screens are compared when the reference trace has screen hashes, as
+written by --trace-screen.
+
+exit status: 0 when the traces match, 1 on a divergence, 2 on bad arguments";
+
+/// Instructions shown before and after the divergence unless `--context` is given
+const DEFAULT_CONTEXT: usize = 5;
+
+/// Prints the divergence and the instructions around it, then exits
+fn diverged(message: &str, history: &VecDeque<TraceLine>, following: &[(usize, TraceLine)]) -> ! {
+    println!("{}", message);
+    if !history.is_empty() {
+        println!("\nlast instructions executed:");
+        for line in history {
+            println!("  {}", line);
+        }
+    }
+    if !following.is_empty() {
+        println!("\nreference from there:");
+        for (number, line) in following {
+            println!("  {:>6}  {}", number, line);
+        }
+    }
+    process::exit(1);
+}
+
+fn main() {
+    let mut paths = Vec::new();
+    let mut script = KeyScript::default();
+    let mut quirks = Quirks::default();
+    let mut cycles_per_frame = CYCLES_PER_FRAME;
+    let mut seed = None;
+    let mut context = DEFAULT_CONTEXT;
+
+    let mut args = Args::new("chip8-difftest", USAGE);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--keys" => script = args.key_script(&arg),
+            "--quirks" => quirks = args.quirks(&arg),
+            "--cycles-per-frame" => cycles_per_frame = args.number(&arg).max(1) as usize,
+            "--seed" => seed = Some(args.number(&arg)),
+            "--context" => context = args.number(&arg) as usize,
+            _ if cli::is_option(&arg) => args.unexpected(&arg),
+            _ if paths.len() < 2 => paths.push(arg),
+            _ => args.unexpected(&arg),
+        }
+    }
+    if paths.len() < 2 {
+        args.usage("missing ROM or reference trace");
+    }
+    let (game_location, trace_location) = (&paths[0], &paths[1]);
+
+    let text = fs::read_to_string(trace_location)
+        .unwrap_or_else(|why| args.usage(&format!("couldn't read {}: {}", trace_location, why)));
+    // each line is kept with its line number in the file
+    let mut reference = Vec::new();
+    for (index, line) in text.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+        let number = index + 1;
+        let line = TraceLine::parse(line)
+            .unwrap_or_else(|why| args.usage(&format!("{}:{}: {}", trace_location, number, why)));
+        if reference.last().is_some_and(|(_, last): &(usize, TraceLine)| last.cycle >= line.cycle) {
+            args.usage(&format!("{}:{}: cycle {} doesn't follow the previous line", trace_location, number, line.cycle));
+        }
+        reference.push((number, line));
+    }
+
+    let mut vm = Vm::init();
+    vm.load_font();
+    vm.quirks = quirks;
+    if let Some(seed) = seed {
+        vm.seed(seed);
+    }
+    if let Err(why) = vm.load_game(game_location) {
+        eprintln!("couldn't load {}: {}", game_location, why);
+        process::exit(2);
+    }
+
+    let following = |index: usize| &reference[index.min(reference.len())..(index + context).min(reference.len())];
+    let mut history = VecDeque::with_capacity(context + 1);
+    // index of the next reference line to compare
+    let mut next = 0;
+    let mut compare = |vm: &Vm, cycle: u64, history: &VecDeque<TraceLine>| {
+        let (number, expected) = match reference.get(next) {
+            Some((number, expected)) if expected.cycle == cycle => (number, expected),
+            _ => return,
+        };
+        next += 1;
+        let actual = TraceLine::capture(vm, cycle, expected.screen.is_some());
+        let differences = expected.differences(&actual);
+        if !differences.is_empty() {
+            let mut message = format!("diverged at cycle {} ({}:{}):", cycle, trace_location, number);
+            for (name, expected, got) in differences {
+                message.push_str(&format!("\n  {:<6} expected {}, got {}", name, expected, got));
+            }
+            message.push_str(&format!("\n\n  reference {}\n  ours      {}", expected, actual));
+            diverged(&message, history, following(next));
+        }
+    };
+
+    // the machine stops before the instruction of the last reference line
+    let cycles = reference.last().map_or(0, |(_, last)| last.cycle);
+    let report = headless::run_with(&mut vm, Limit::Cycles(cycles), cycles_per_frame, &script, &mut NullFrontend,
+                                    |vm, cycle| {
+        compare(vm, cycle, &history);
+        history.push_back(TraceLine::capture(vm, cycle, false));
+        if history.len() > context {
+            history.pop_front();
+        }
+    }, |_| {});
+
+    let how = match report.status {
+        RunStatus::Completed => {
+            compare(&vm, report.cycles, &history);
+            println!("traces match over {} lines, up to cycle {}", reference.len(), report.cycles);
+            return;
+        }
+        RunStatus::Exited => "exited".to_string(),
+        RunStatus::Fault(error) => format!("faulted ({})", error),
+    };
+    let message = format!("the machine {} at cycle {}, before cycle {} of the reference",
+                          how, report.cycles - 1, reference[next].1.cycle);
+    diverged(&message, &history, following(next));
+}
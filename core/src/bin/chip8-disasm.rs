@@ -0,0 +1,38 @@
+//! Prints a ROM as a listing of address, raw bytes and mnemonic
+
+extern crate chip8_core;
+
+use std::process;
+
+use chip8_core::cli::{self, Args};
+use chip8_core::disasm;
+use chip8_core::loader;
+
+const USAGE: &str = "usage: chip8-disasm [--linear] <path-to-ROM>
+
+Bytes that are never reached from 0x200 are listed as data.
+  --linear    decode every byte as code, without tracing control flow";
+
+fn main() {
+    let mut game_location = None;
+    let mut trace = true;
+
+    let mut args = Args::new("chip8-disasm", USAGE);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--linear" => trace = false,
+            _ if cli::is_option(&arg) => args.unexpected(&arg),
+            _ if game_location.is_none() => game_location = Some(arg),
+            _ => args.unexpected(&arg),
+        }
+    }
+    let game_location = game_location.unwrap_or_else(|| args.usage("missing ROM"));
+
+    match loader::read_rom(&game_location) {
+        Ok(rom) => print!("{}", disasm::listing(&rom, trace)),
+        Err(why) => {
+            eprintln!("couldn't load {}: {}", game_location, why);
+            process::exit(1);
+        }
+    }
+}
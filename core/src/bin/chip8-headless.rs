@@ -0,0 +1,162 @@
+//! Runs a ROM without a display or an audio device
+//!
+//! Meant for CI: the ROM runs for a fixed number of cycles or frames,
+//! optionally with scripted key input, and the final state is written out.
+
+extern crate chip8_core;
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::process;
+
+use chip8_core::cli::{self, Args};
+use chip8_core::headless::{self, KeyScript, Limit, RunStatus};
+use chip8_core::trace::{TraceFilter, Tracer};
+use chip8_core::vm::CYCLES_PER_FRAME;
+use chip8_core::{Frame, NullFrontend, Palette, Quirks, Recorder, Vm};
+
+const USAGE: &str = "usage: chip8-headless <path-to-ROM> (--cycles <n> | --frames <n>) [options]
+
+options:
+  --keys <file>          scripted key input, lines of '<frame> <down|up> <key>'
+  --quirks <profile>     quirks profile
+  --cycles-per-frame <n> instructions per 60 Hz frame (default 10)
+  --seed <n>             seed of the random number generator
+  --trace <file>         log every executed instruction
+  --trace-pc <from-to>   only log the instructions at these hex addresses
+  --trace-ops <classes>  only log these opcode classes, e.g. 'D,F'
+  --trace-screen         add a hash of the screen to every logged instruction";
+
+const OUTPUT_USAGE: &str = "
+output (use '-' for stdout):
+  --ascii <file>         final framebuffer as ASCII art
+  --png <file>           final framebuffer as PNG image
+  --record <file>        every frame as animated PNG (APNG)
+  --scale <n>            scale of the images (default 1)
+  --palette <theme>      colours of the images, a theme name or 'RRGGBB,RRGGBB[,RRGGBB,RRGGBB]'
+  --registers <file>     registers and exit status as JSON
+
+exit status: 0 when the limit was reached or the ROM exited, 1 on a fault,
+2 on bad arguments";
+
+/// Opens `path` for writing, `-` being stdout
+fn create(path: &str) -> Box<dyn Write> {
+    if path == "-" {
+        return Box::new(io::stdout());
+    }
+    match File::create(path) {
+        Ok(file) => Box::new(BufWriter::new(file)),
+        Err(why) => {
+            eprintln!("couldn't create {}: {}", path, why);
+            process::exit(2);
+        }
+    }
+}
+
+fn main() {
+    let mut game_location = None;
+    let mut limit = None;
+    let mut script = KeyScript::default();
+    let mut quirks = Quirks::default();
+    let mut cycles_per_frame = CYCLES_PER_FRAME;
+    let mut seed = None;
+    let mut tracer = None;
+    let mut trace_filter = TraceFilter::default();
+    let mut trace_screen = false;
+    let mut ascii = None;
+    let mut png = None;
+    let mut record = None;
+    let mut scale = 1;
+    let mut palette = Palette::default();
+    let mut registers = None;
+
+    let mut args = Args::new("chip8-headless", &format!("{}{}", USAGE, OUTPUT_USAGE));
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--cycles" => limit = Some(Limit::Cycles(args.number(&arg))),
+            "--frames" => limit = Some(Limit::Frames(args.number(&arg))),
+            "--keys" => script = args.key_script(&arg),
+            "--quirks" => quirks = args.quirks(&arg),
+            "--cycles-per-frame" => cycles_per_frame = args.number(&arg).max(1) as usize,
+            "--seed" => seed = Some(args.number(&arg)),
+            "--trace" => {
+                let path = args.value(&arg);
+                tracer = Some(Tracer::create(&path)
+                    .unwrap_or_else(|why| args.usage(&format!("couldn't create {}: {}", path, why))));
+            }
+            "--trace-pc" => {
+                let pcs = TraceFilter::parse_pcs(&args.value(&arg))
+                    .unwrap_or_else(|why| args.usage(&format!("--trace-pc: {}", why)));
+                trace_filter.pcs = Some(pcs);
+            }
+            "--trace-screen" => trace_screen = true,
+            "--trace-ops" => {
+                trace_filter.classes = TraceFilter::parse_classes(&args.value(&arg))
+                    .unwrap_or_else(|why| args.usage(&format!("--trace-ops: {}", why)));
+            }
+            "--ascii" => ascii = Some(args.value(&arg)),
+            "--png" => png = Some(args.value(&arg)),
+            "--record" => record = Some(args.value(&arg)),
+            "--scale" => scale = args.number(&arg).max(1) as usize,
+            "--palette" => {
+                palette = Palette::parse(&args.value(&arg))
+                    .unwrap_or_else(|why| args.usage(&format!("invalid palette: {}", why)));
+            }
+            "--registers" => registers = Some(args.value(&arg)),
+            _ if cli::is_option(&arg) => args.unexpected(&arg),
+            _ if game_location.is_none() => game_location = Some(arg),
+            _ => args.unexpected(&arg),
+        }
+    }
+    let game_location = game_location.unwrap_or_else(|| args.usage("missing ROM"));
+    let limit = limit.unwrap_or_else(|| args.usage("missing --cycles or --frames"));
+
+    let mut vm = Vm::init();
+    vm.load_font();
+    vm.quirks = quirks;
+    if let Some(seed) = seed {
+        vm.seed(seed);
+    }
+    if let Err(why) = vm.load_game(&game_location) {
+        eprintln!("couldn't load {}: {}", game_location, why);
+        process::exit(2);
+    }
+
+    if let Some(tracer) = tracer.as_mut() {
+        tracer.set_filter(trace_filter);
+        tracer.set_screen_hash(trace_screen);
+    }
+    let mut recorder = record.as_ref().map(|_| Recorder::new(palette, scale));
+    let trace = |vm: &Vm, _| tracer.iter_mut().for_each(|tracer| tracer.record(vm));
+    let capture = |vm: &Vm| {
+        if let Some(recorder) = recorder.as_mut() {
+            recorder.capture(vm);
+        }
+    };
+    let report = headless::run_with(&mut vm, limit, cycles_per_frame, &script, &mut NullFrontend, trace, capture);
+    if let RunStatus::Fault(error) = report.status {
+        eprintln!("Fault at PC 0x{:03X} (opcode 0x{:04X}): {}", vm.pc, vm.opcode, error);
+    }
+
+    let frame = Frame::capture(&vm);
+    let written = tracer.map_or(Ok(()), Tracer::finish)
+        .and_then(|_| ascii.map_or(Ok(()), |path| create(&path).write_all(frame.to_ascii().as_bytes())))
+        .and_then(|_| png.map_or(Ok(()), |path| frame.write_png(create(&path), &palette, scale)))
+        .and_then(|_| match (record, recorder) {
+            (Some(path), Some(recorder)) if !recorder.is_empty() => recorder.write(create(&path)),
+            _ => Ok(()),
+        })
+        .and_then(|_| {
+            registers.map_or(Ok(()), |path| {
+                create(&path).write_all(headless::registers_json(&vm, &report).as_bytes())
+            })
+        });
+    if let Err(why) = written {
+        eprintln!("couldn't write output: {}", why);
+        process::exit(2);
+    }
+
+    if let RunStatus::Fault(_) = report.status {
+        process::exit(1);
+    }
+}
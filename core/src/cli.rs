@@ -0,0 +1,94 @@
+//! Argument parsing shared by the command line tools in `src/bin`
+
+use std::env;
+use std::fs;
+use std::process;
+
+use crate::headless::KeyScript;
+use crate::quirks::{Quirks, PROFILES};
+
+/// Arguments of a tool, without the program name
+///
+/// Iterating yields the arguments one by one; `-h` and `--help` print the
+/// usage and exit. Every error prints the usage to stderr and exits with
+/// status 2.
+pub struct Args {
+    program: &'static str,
+    usage: String,
+    args: env::Args,
+}
+
+impl Args {
+    pub fn new(program: &'static str, usage: &str) -> Args {
+        let mut args = env::args();
+        args.next();
+        Args {
+            program,
+            usage: usage.to_string(),
+            args,
+        }
+    }
+
+    /// Prints the error and the usage and exits
+    pub fn usage(&self, error: &str) -> ! {
+        eprintln!("{}: {}\n{}", self.program, error, self.usage);
+        process::exit(2);
+    }
+
+    /// Rejects `arg`, as an unknown option when it looks like one
+    pub fn unexpected(&self, arg: &str) -> ! {
+        if is_option(arg) {
+            self.usage(&format!("unknown option '{}'", arg))
+        } else {
+            self.usage(&format!("unexpected argument '{}'", arg))
+        }
+    }
+
+    /// Takes the value following `option`
+    pub fn value(&mut self, option: &str) -> String {
+        match self.args.next() {
+            Some(value) => value,
+            None => self.usage(&format!("{} needs a value", option)),
+        }
+    }
+
+    /// Takes the value following `option` as a decimal number
+    pub fn number(&mut self, option: &str) -> u64 {
+        let value = self.value(option);
+        value.parse().unwrap_or_else(|_| self.usage(&format!("invalid number '{}'", value)))
+    }
+
+    /// Reads the key script named by the value following `option`
+    pub fn key_script(&mut self, option: &str) -> KeyScript {
+        let path = self.value(option);
+        let text = fs::read_to_string(&path)
+            .unwrap_or_else(|why| self.usage(&format!("couldn't read {}: {}", path, why)));
+        KeyScript::parse(&text).unwrap_or_else(|why| self.usage(&format!("{}: {}", path, why)))
+    }
+
+    /// Looks up the quirks profile named by the value following `option`
+    pub fn quirks(&mut self, option: &str) -> Quirks {
+        let name = self.value(option);
+        Quirks::from_name(&name).unwrap_or_else(|| {
+            self.usage(&format!("unknown quirks profile '{}', expected one of {}", name, PROFILES.join(", ")))
+        })
+    }
+}
+
+/// Whether `arg` looks like an option rather than a path, `-` being stdout
+pub fn is_option(arg: &str) -> bool {
+    arg.starts_with('-') && arg != "-"
+}
+
+impl Iterator for Args {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        let arg = self.args.next()?;
+        if arg == "-h" || arg == "--help" {
+            println!("{}", self.usage);
+            process::exit(0);
+        }
+        Some(arg)
+    }
+}
@@ -0,0 +1,45 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Rate of the delay and sound timers, and of the display, in Hz
+pub const FRAME_RATE: u32 = 60;
+
+/// Number of frames the clock may fall behind before it gives up
+/// catching up, e.g. after the process was suspended
+const MAX_LAG_FRAMES: u32 = 5;
+
+/// Paces a loop to a fixed frame rate
+///
+/// Frames are scheduled on absolute deadlines, so oversleeping one frame
+/// shortens the wait for the next one instead of slowing the game down.
+pub struct FrameClock {
+    period: Duration,
+    next: Instant,
+}
+
+impl FrameClock {
+    /// Creates a clock ticking `rate` times per second, starting now
+    pub fn new(rate: u32) -> FrameClock {
+        FrameClock {
+            period: Duration::from_secs(1) / rate,
+            next: Instant::now(),
+        }
+    }
+
+    /// Sleeps until the next frame is due
+    pub fn wait(&mut self) {
+        let now = Instant::now();
+        if now < self.next {
+            thread::sleep(self.next - now);
+        } else if now - self.next > self.period * MAX_LAG_FRAMES {
+            self.next = now;
+        }
+        self.next += self.period;
+    }
+}
+
+impl Default for FrameClock {
+    fn default() -> FrameClock {
+        FrameClock::new(FRAME_RATE)
+    }
+}
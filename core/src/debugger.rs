@@ -0,0 +1,356 @@
+use std::collections::BTreeSet;
+use std::fmt;
+use std::fmt::Write;
+
+use crate::disasm;
+use crate::error::{CycleOutcome, VmError};
+use crate::frontend::Frontend;
+use crate::vm::{Vm, RAM_SIZE};
+
+/// Commands understood by `Debugger::execute`
+pub const HELP: &str = "\
+commands:
+  c, continue          run until a breakpoint or watch is hit
+  s, step              execute one instruction
+  n, next              step over a subroutine call (2NNN)
+  o, out               run until the current subroutine returns (00EE)
+  b, break <addr>      set a breakpoint on PC
+  d, delete <addr>     remove a breakpoint
+  w, watch <addr> [n]  stop when n bytes (default 1) at addr are written
+  u, unwatch <addr>    remove the watches starting at addr
+  l, list              list breakpoints and watches
+  r, regs              show the registers, stack and timers
+  m, mem <addr> [n]    show n bytes (default 64) of RAM
+  q, quit              quit the emulator
+addresses and lengths are hex, e.g. 'b 2a4' or 'm 0x300 20'";
+
+/// Why the debugger paused the machine
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    /// PC reached a breakpoint, the instruction there wasn't executed yet
+    Breakpoint(usize),
+    /// The instruction at `pc` wrote to a watched range
+    Watch { pc: usize, addr: usize, len: usize },
+    /// A step, step over or step out finished
+    Step,
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            StopReason::Breakpoint(addr) => write!(f, "breakpoint at 0x{:03X}", addr),
+            StopReason::Watch { pc, addr, len } => {
+                write!(f, "instruction at 0x{:03X} wrote {} byte(s) at 0x{:03X}", pc, len, addr)
+            }
+            StopReason::Step => write!(f, "stepped"),
+        }
+    }
+}
+
+/// What the frontend should do after a command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// Keep reading commands
+    Prompt,
+    /// Resume running the machine
+    Resume,
+    /// Quit the emulator
+    Quit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Paused,
+    Running,
+    Step,
+    /// Running until the call at the paused PC returns to `pc` with stack depth `sp`
+    StepOver { pc: usize, sp: usize },
+    /// Running until the stack depth drops below `sp`
+    StepOut { sp: usize },
+}
+
+/// Step debugger driving a `Vm`
+///
+/// The frontend calls `run_frame` instead of `Vm::run_frame`. Whenever
+/// the debugger pauses, `is_paused` turns true and the frontend reads
+/// commands for `execute` until one of them resumes the machine.
+pub struct Debugger {
+    mode: Mode,
+    breakpoints: BTreeSet<usize>,
+    /// Watched memory ranges as start address and length
+    watches: Vec<(usize, usize)>,
+    /// Breakpoint the machine is paused on, skipped once when resuming
+    resume_from: Option<usize>,
+    stop: Option<StopReason>,
+}
+
+impl Debugger {
+    /// Creates a debugger with the machine paused
+    pub fn new() -> Debugger {
+        Debugger {
+            mode: Mode::Paused,
+            breakpoints: BTreeSet::new(),
+            watches: Vec::new(),
+            resume_from: None,
+            stop: None,
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.mode == Mode::Paused
+    }
+
+    /// Pauses the machine before its next instruction
+    pub fn pause(&mut self) {
+        self.mode = Mode::Paused;
+    }
+
+    /// Returns why the machine was paused, once per pause
+    pub fn take_stop_reason(&mut self) -> Option<StopReason> {
+        self.stop.take()
+    }
+
+    /// Runs one frame of up to `cycles` instructions, stopping early when
+    /// the debugger pauses
+    ///
+    /// The timers only tick when the whole frame ran.
+    pub fn run_frame<F: Frontend>(&mut self, vm: &mut Vm, cycles: usize, frontend: &mut F) -> Result<CycleOutcome, VmError> {
+        self.run_frame_with(vm, cycles, frontend, |_| {})
+    }
+
+    /// Like `run_frame`, calling `before_cycle` ahead of every instruction
+    /// that is actually executed
+    pub fn run_frame_with<F, C>(&mut self, vm: &mut Vm, cycles: usize, frontend: &mut F, mut before_cycle: C)
+        -> Result<CycleOutcome, VmError>
+        where F: Frontend, C: FnMut(&Vm) {
+        let mut outcome = CycleOutcome::Executed;
+        for _ in 0..cycles {
+            if self.is_paused() {
+                return Ok(outcome);
+            }
+            if !self.stops_at(vm.pc) {
+                before_cycle(vm);
+            }
+            outcome = self.cycle(vm)?;
+            if outcome == CycleOutcome::Exit {
+                return Ok(outcome);
+            }
+        }
+        if !self.is_paused() {
+            vm.update_timers(frontend);
+        }
+        Ok(outcome)
+    }
+
+    /// Whether a breakpoint stops the machine before executing the instruction at `pc`
+    fn stops_at(&self, pc: usize) -> bool {
+        self.resume_from != Some(pc) && self.mode != Mode::Step && self.breakpoints.contains(&pc)
+    }
+
+    /// Executes one instruction, unless the machine is paused or PC hit a breakpoint
+    pub fn cycle(&mut self, vm: &mut Vm) -> Result<CycleOutcome, VmError> {
+        if self.mode == Mode::Paused {
+            return Ok(CycleOutcome::Executed);
+        }
+        if self.stops_at(vm.pc) {
+            self.resume_from = None;
+            self.stop_with(StopReason::Breakpoint(vm.pc));
+            return Ok(CycleOutcome::Executed);
+        }
+        self.resume_from = None;
+
+        let pc = vm.pc;
+        let outcome = vm.emulate_cycle();
+        if outcome.is_err() {
+            self.mode = Mode::Paused;
+            return outcome;
+        }
+
+        if let Some((addr, len)) = vm.last_write {
+            let hit = self.watches.iter().any(|&(start, count)| addr < start + count && start < addr + len);
+            if hit {
+                self.stop_with(StopReason::Watch { pc, addr, len });
+                return outcome;
+            }
+        }
+        let done = match self.mode {
+            Mode::Step => true,
+            Mode::StepOver { pc, sp } => vm.pc == pc && vm.sp == sp,
+            Mode::StepOut { sp } => vm.sp < sp,
+            Mode::Paused | Mode::Running => false,
+        };
+        if done {
+            self.stop_with(StopReason::Step);
+        }
+        outcome
+    }
+
+    fn stop_with(&mut self, reason: StopReason) {
+        self.mode = Mode::Paused;
+        self.stop = Some(reason);
+    }
+
+    fn resume(&mut self, vm: &Vm, mode: Mode) -> Action {
+        self.mode = mode;
+        self.resume_from = Some(vm.pc);
+        Action::Resume
+    }
+
+    /// Runs one debugger command, writing its output to `out`
+    pub fn execute(&mut self, line: &str, vm: &mut Vm, out: &mut String) -> Action {
+        let mut words = line.split_whitespace();
+        let command = match words.next() {
+            Some(command) => command,
+            None => return Action::Prompt,
+        };
+        let args: Vec<&str> = words.collect();
+
+        let parsed = args.iter().map(|arg| parse_hex(arg)).collect::<Option<Vec<usize>>>();
+        let numbers = match parsed {
+            Some(numbers) => numbers,
+            None => {
+                let _ = writeln!(out, "invalid number in '{}'", line.trim());
+                return Action::Prompt;
+            }
+        };
+
+        match (command, numbers.as_slice()) {
+            ("c", []) | ("continue", []) => self.resume(vm, Mode::Running),
+            ("s", []) | ("step", []) => self.resume(vm, Mode::Step),
+            ("n", []) | ("next", []) => {
+                let call = vm.ram.get(vm.pc).is_some_and(|&byte| byte & 0xF0 == 0x20);
+                if call {
+                    let mode = Mode::StepOver { pc: vm.pc + 2, sp: vm.sp };
+                    self.resume(vm, mode)
+                } else {
+                    self.resume(vm, Mode::Step)
+                }
+            }
+            ("o", []) | ("out", []) => {
+                if vm.sp == 0 {
+                    let _ = writeln!(out, "not in a subroutine");
+                    return Action::Prompt;
+                }
+                let mode = Mode::StepOut { sp: vm.sp };
+                self.resume(vm, mode)
+            }
+            ("b", [addr]) | ("break", [addr]) => {
+                self.breakpoints.insert(*addr);
+                let _ = writeln!(out, "breakpoint at 0x{:03X}", addr);
+                Action::Prompt
+            }
+            ("d", [addr]) | ("delete", [addr]) => {
+                if !self.breakpoints.remove(addr) {
+                    let _ = writeln!(out, "no breakpoint at 0x{:03X}", addr);
+                }
+                Action::Prompt
+            }
+            ("w", [addr]) | ("watch", [addr]) => self.watch(*addr, 1, out),
+            ("w", [addr, len]) | ("watch", [addr, len]) => self.watch(*addr, *len, out),
+            ("u", [addr]) | ("unwatch", [addr]) => {
+                self.watches.retain(|&(start, _)| start != *addr);
+                Action::Prompt
+            }
+            ("l", []) | ("list", []) => {
+                for addr in &self.breakpoints {
+                    let _ = writeln!(out, "breakpoint 0x{:03X}", addr);
+                }
+                for &(addr, len) in &self.watches {
+                    let _ = writeln!(out, "watch 0x{:03X}..0x{:03X}", addr, addr + len);
+                }
+                Action::Prompt
+            }
+            ("r", []) | ("regs", []) => {
+                out.push_str(&registers(vm));
+                Action::Prompt
+            }
+            ("m", [addr]) | ("mem", [addr]) | ("m", [addr, _]) | ("mem", [addr, _]) if *addr >= RAM_SIZE => {
+                let _ = writeln!(out, "address outside of RAM");
+                Action::Prompt
+            }
+            ("m", [addr]) | ("mem", [addr]) => {
+                out.push_str(&hex_dump(vm, *addr, 64));
+                Action::Prompt
+            }
+            ("m", [addr, len]) | ("mem", [addr, len]) => {
+                out.push_str(&hex_dump(vm, *addr, *len));
+                Action::Prompt
+            }
+            ("q", []) | ("quit", []) => Action::Quit,
+            ("h", []) | ("help", []) => {
+                let _ = writeln!(out, "{}", HELP);
+                Action::Prompt
+            }
+            _ => {
+                let _ = writeln!(out, "unknown command '{}', try 'help'", line.trim());
+                Action::Prompt
+            }
+        }
+    }
+
+    fn watch(&mut self, addr: usize, len: usize, out: &mut String) -> Action {
+        let inside = addr < RAM_SIZE && addr.checked_add(len).is_some_and(|end| end <= RAM_SIZE);
+        if len == 0 || !inside {
+            let _ = writeln!(out, "watch outside of RAM");
+        } else {
+            self.watches.push((addr, len));
+        }
+        Action::Prompt
+    }
+}
+
+impl Default for Debugger {
+    fn default() -> Debugger {
+        Debugger::new()
+    }
+}
+
+/// Parses a hex number, with or without a `0x` prefix
+fn parse_hex(text: &str) -> Option<usize> {
+    let digits = text.trim_start_matches("0x").trim_start_matches("0X");
+    usize::from_str_radix(digits, 16).ok()
+}
+
+/// Describes the registers, stack and timers
+pub fn registers(vm: &Vm) -> String {
+    let mut text = String::new();
+    let instruction = match disasm::decode_at(&vm.ram, vm.pc) {
+        Some((instruction, _)) => instruction.to_string(),
+        None => "----".to_string(),
+    };
+
+    let _ = writeln!(text, "PC  {:04X} [{}]  I  {:04X}  DT {:02X}  ST {:02X}",
+                     vm.pc, instruction, vm.i, vm.delay_timer, vm.sound_timer);
+    for row in 0..2 {
+        for index in row * 8..row * 8 + 8 {
+            let _ = write!(text, "V{:X} {:02X}  ", index, vm.v[index]);
+        }
+        text.truncate(text.trim_end().len());
+        text.push('\n');
+    }
+    let _ = write!(text, "SP  {:X}  stack:", vm.sp);
+    for addr in &vm.stack[..vm.sp] {
+        let _ = write!(text, " {:04X}", addr);
+    }
+    text.push('\n');
+    text
+}
+
+/// Shows `len` bytes of RAM starting at `addr`, 16 bytes per line,
+/// stopping at the end of RAM
+pub fn hex_dump(vm: &Vm, addr: usize, len: usize) -> String {
+    let mut text = String::new();
+    if addr >= RAM_SIZE {
+        return text;
+    }
+    let end = addr.checked_add(len).map_or(RAM_SIZE, |end| end.min(RAM_SIZE));
+
+    for line in (addr..end).step_by(16) {
+        let _ = write!(text, "{:04X}:", line);
+        for byte in &vm.ram[line..(line + 16).min(end)] {
+            let _ = write!(text, " {:02X}", byte);
+        }
+        text.push('\n');
+    }
+    text
+}
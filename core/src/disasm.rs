@@ -0,0 +1,455 @@
+use std::fmt;
+use std::fmt::Write;
+
+use crate::vm::PROGRAM_START;
+
+/// Decoded CHIP-8, SUPER-CHIP or XO-CHIP instruction
+///
+/// Register operands are register numbers (`0x0` - `0xF`). Variants are
+/// named after the handlers in `opcodes.rs`, and `Display` uses the same
+/// Cowgod-style mnemonics documented there.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    /// 00CN
+    ScdN(u8),
+    /// 00DN
+    ScuN(u8),
+    /// 00E0
+    Cls,
+    /// 00EE
+    Ret,
+    /// 00FB
+    Scr,
+    /// 00FC
+    Scl,
+    /// 00FD
+    Exit,
+    /// 00FE
+    Low,
+    /// 00FF
+    High,
+    /// 1NNN
+    JpAddr(u16),
+    /// 2NNN
+    CallAddr(u16),
+    /// 3XNN
+    SeVxByte(u8, u8),
+    /// 4XNN
+    SneVxByte(u8, u8),
+    /// 5XY0
+    SeVxVy(u8, u8),
+    /// 5XY2
+    SaveVxVy(u8, u8),
+    /// 5XY3
+    LoadVxVy(u8, u8),
+    /// 6XNN
+    LdVxByte(u8, u8),
+    /// 7XNN
+    AddVxByte(u8, u8),
+    /// 8XY0
+    LdVxVy(u8, u8),
+    /// 8XY1
+    OrVxVy(u8, u8),
+    /// 8XY2
+    AndVxVy(u8, u8),
+    /// 8XY3
+    XorVxVy(u8, u8),
+    /// 8XY4
+    AddVxVy(u8, u8),
+    /// 8XY5
+    SubVxVy(u8, u8),
+    /// 8XY6
+    ShrVxVy(u8, u8),
+    /// 8XY7
+    SubnVxVy(u8, u8),
+    /// 8XYE
+    ShlVxVy(u8, u8),
+    /// 9XY0
+    SneVxVy(u8, u8),
+    /// ANNN
+    LdIAddr(u16),
+    /// BNNN
+    JpV0Addr(u16),
+    /// CXNN
+    RndVxByte(u8, u8),
+    /// DXYN
+    DrwVxVyN(u8, u8, u8),
+    /// EX9E
+    SkpVx(u8),
+    /// EXA1
+    SknpVx(u8),
+    /// F000 NNNN, the address being stored in the two bytes after the opcode
+    LdILong(u16),
+    /// FN01
+    PlaneN(u8),
+    /// F002
+    Audio,
+    /// FX07
+    LdVxDt(u8),
+    /// FX0A
+    LdVxK(u8),
+    /// FX15
+    LdDtVx(u8),
+    /// FX18
+    LdStVx(u8),
+    /// FX1E
+    AddIVx(u8),
+    /// FX29
+    LdFVx(u8),
+    /// FX30
+    LdHfVx(u8),
+    /// FX33
+    LdBVx(u8),
+    /// FX3A
+    PitchVx(u8),
+    /// FX55
+    LdIVx(u8),
+    /// FX65
+    LdVxI(u8),
+    /// FX75
+    LdRVx(u8),
+    /// FX85
+    LdVxR(u8),
+    /// Opcode that doesn't decode to any instruction
+    Unknown(u16),
+}
+
+/// Decodes one opcode
+///
+/// `F000` decodes to `LdILong(0)`, as its address is stored in the next
+/// two bytes; use `decode_at` to decode it from memory.
+pub fn disassemble(opcode: u16) -> Instruction {
+    use self::Instruction::*;
+
+    let x = ((opcode & 0x0F00) >> 8) as u8;
+    let y = ((opcode & 0x00F0) >> 4) as u8;
+    let n = (opcode & 0x000F) as u8;
+    let nn = (opcode & 0x00FF) as u8;
+    let nnn = opcode & 0x0FFF;
+
+    match opcode & 0xF000 {
+        0x0000 => match nnn {
+            0x00C0..=0x00CF => ScdN(n),
+            0x00D0..=0x00DF => ScuN(n),
+            0x00E0 => Cls,
+            0x00EE => Ret,
+            0x00FB => Scr,
+            0x00FC => Scl,
+            0x00FD => Exit,
+            0x00FE => Low,
+            0x00FF => High,
+            _ => Unknown(opcode),
+        },
+        0x1000 => JpAddr(nnn),
+        0x2000 => CallAddr(nnn),
+        0x3000 => SeVxByte(x, nn),
+        0x4000 => SneVxByte(x, nn),
+        0x5000 => match n {
+            0x0 => SeVxVy(x, y),
+            0x2 => SaveVxVy(x, y),
+            0x3 => LoadVxVy(x, y),
+            _ => Unknown(opcode),
+        },
+        0x6000 => LdVxByte(x, nn),
+        0x7000 => AddVxByte(x, nn),
+        0x8000 => match n {
+            0x0 => LdVxVy(x, y),
+            0x1 => OrVxVy(x, y),
+            0x2 => AndVxVy(x, y),
+            0x3 => XorVxVy(x, y),
+            0x4 => AddVxVy(x, y),
+            0x5 => SubVxVy(x, y),
+            0x6 => ShrVxVy(x, y),
+            0x7 => SubnVxVy(x, y),
+            0xE => ShlVxVy(x, y),
+            _ => Unknown(opcode),
+        },
+        0x9000 if n == 0 => SneVxVy(x, y),
+        0xA000 => LdIAddr(nnn),
+        0xB000 => JpV0Addr(nnn),
+        0xC000 => RndVxByte(x, nn),
+        0xD000 => DrwVxVyN(x, y, n),
+        0xE000 => match nn {
+            0x9E => SkpVx(x),
+            0xA1 => SknpVx(x),
+            _ => Unknown(opcode),
+        },
+        0xF000 => match nn {
+            0x00 if opcode == 0xF000 => LdILong(0),
+            0x01 => PlaneN(x),
+            0x02 if opcode == 0xF002 => Audio,
+            0x07 => LdVxDt(x),
+            0x0A => LdVxK(x),
+            0x15 => LdDtVx(x),
+            0x18 => LdStVx(x),
+            0x1E => AddIVx(x),
+            0x29 => LdFVx(x),
+            0x30 => LdHfVx(x),
+            0x33 => LdBVx(x),
+            0x3A => PitchVx(x),
+            0x55 => LdIVx(x),
+            0x65 => LdVxI(x),
+            0x75 => LdRVx(x),
+            0x85 => LdVxR(x),
+            _ => Unknown(opcode),
+        },
+        _ => Unknown(opcode),
+    }
+}
+
+/// Decodes the instruction stored at `addr` in `memory`,
+/// returning it along with its length in bytes
+pub fn decode_at(memory: &[u8], addr: usize) -> Option<(Instruction, usize)> {
+    let word = |at: usize| -> Option<u16> {
+        let bytes = memory.get(at..at + 2)?;
+        Some((bytes[0] as u16) << 8 | bytes[1] as u16)
+    };
+
+    match disassemble(word(addr)?) {
+        Instruction::LdILong(_) => Some((Instruction::LdILong(word(addr + 2)?), 4)),
+        instruction => Some((instruction, 2)),
+    }
+}
+
+impl Instruction {
+    /// Whether the instruction may skip the one following it
+    pub fn is_skip(&self) -> bool {
+        use self::Instruction::*;
+
+        matches!(self, SeVxByte(..) | SneVxByte(..) | SeVxVy(..) | SneVxVy(..) | SkpVx(_) | SknpVx(_))
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::Instruction::*;
+
+        match *self {
+            ScdN(n) => write!(f, "SCD {}", n),
+            ScuN(n) => write!(f, "SCU {}", n),
+            Cls => write!(f, "CLS"),
+            Ret => write!(f, "RET"),
+            Scr => write!(f, "SCR"),
+            Scl => write!(f, "SCL"),
+            Exit => write!(f, "EXIT"),
+            Low => write!(f, "LOW"),
+            High => write!(f, "HIGH"),
+            JpAddr(addr) => write!(f, "JP 0x{:03X}", addr),
+            CallAddr(addr) => write!(f, "CALL 0x{:03X}", addr),
+            SeVxByte(x, byte) => write!(f, "SE V{:X}, 0x{:02X}", x, byte),
+            SneVxByte(x, byte) => write!(f, "SNE V{:X}, 0x{:02X}", x, byte),
+            SeVxVy(x, y) => write!(f, "SE V{:X}, V{:X}", x, y),
+            SaveVxVy(x, y) => write!(f, "SAVE V{:X}, V{:X}", x, y),
+            LoadVxVy(x, y) => write!(f, "LOAD V{:X}, V{:X}", x, y),
+            LdVxByte(x, byte) => write!(f, "LD V{:X}, 0x{:02X}", x, byte),
+            AddVxByte(x, byte) => write!(f, "ADD V{:X}, 0x{:02X}", x, byte),
+            LdVxVy(x, y) => write!(f, "LD V{:X}, V{:X}", x, y),
+            OrVxVy(x, y) => write!(f, "OR V{:X}, V{:X}", x, y),
+            AndVxVy(x, y) => write!(f, "AND V{:X}, V{:X}", x, y),
+            XorVxVy(x, y) => write!(f, "XOR V{:X}, V{:X}", x, y),
+            AddVxVy(x, y) => write!(f, "ADD V{:X}, V{:X}", x, y),
+            SubVxVy(x, y) => write!(f, "SUB V{:X}, V{:X}", x, y),
+            ShrVxVy(x, y) => write!(f, "SHR V{:X}, V{:X}", x, y),
+            SubnVxVy(x, y) => write!(f, "SUBN V{:X}, V{:X}", x, y),
+            ShlVxVy(x, y) => write!(f, "SHL V{:X}, V{:X}", x, y),
+            SneVxVy(x, y) => write!(f, "SNE V{:X}, V{:X}", x, y),
+            LdIAddr(addr) => write!(f, "LD I, 0x{:03X}", addr),
+            JpV0Addr(addr) => write!(f, "JP V0, 0x{:03X}", addr),
+            RndVxByte(x, byte) => write!(f, "RND V{:X}, 0x{:02X}", x, byte),
+            DrwVxVyN(x, y, n) => write!(f, "DRW V{:X}, V{:X}, {}", x, y, n),
+            SkpVx(x) => write!(f, "SKP V{:X}", x),
+            SknpVx(x) => write!(f, "SKNP V{:X}", x),
+            LdILong(addr) => write!(f, "LD I, LONG 0x{:04X}", addr),
+            PlaneN(n) => write!(f, "PLANE {}", n),
+            Audio => write!(f, "AUDIO"),
+            LdVxDt(x) => write!(f, "LD V{:X}, DT", x),
+            LdVxK(x) => write!(f, "LD V{:X}, K", x),
+            LdDtVx(x) => write!(f, "LD DT, V{:X}", x),
+            LdStVx(x) => write!(f, "LD ST, V{:X}", x),
+            AddIVx(x) => write!(f, "ADD I, V{:X}", x),
+            LdFVx(x) => write!(f, "LD F, V{:X}", x),
+            LdHfVx(x) => write!(f, "LD HF, V{:X}", x),
+            LdBVx(x) => write!(f, "LD B, V{:X}", x),
+            PitchVx(x) => write!(f, "PITCH V{:X}", x),
+            LdIVx(x) => write!(f, "LD [I], V{:X}", x),
+            LdVxI(x) => write!(f, "LD V{:X}, [I]", x),
+            LdRVx(x) => write!(f, "LD R, V{:X}", x),
+            LdVxR(x) => write!(f, "LD V{:X}, R", x),
+            Unknown(opcode) => write!(f, "DW 0x{:04X}", opcode),
+        }
+    }
+}
+
+/// Marks the bytes of `rom` reachable as code when execution starts at
+/// `PROGRAM_START`
+///
+/// Control flow is traced recursively through jumps, calls and both
+/// outcomes of the skip instructions. `BNNN` is followed to `NNN` only,
+/// as the offset in V0 isn't known statically.
+pub fn trace_code(rom: &[u8]) -> Vec<bool> {
+    use self::Instruction::*;
+
+    let mut code = vec![false; rom.len()];
+    let mut visited = vec![false; rom.len()];
+    let mut pending = vec![PROGRAM_START];
+
+    while let Some(addr) = pending.pop() {
+        let offset = match addr.checked_sub(PROGRAM_START) {
+            Some(offset) if offset < rom.len() && !visited[offset] => offset,
+            _ => continue,
+        };
+        visited[offset] = true;
+
+        let (instruction, len) = match decode_at(rom, offset) {
+            Some((Unknown(_), _)) | None => continue,
+            Some(decoded) => decoded,
+        };
+        for flag in &mut code[offset..offset + len] {
+            *flag = true;
+        }
+
+        let next = addr + len;
+        match instruction {
+            JpAddr(target) | JpV0Addr(target) => pending.push(target as usize),
+            CallAddr(target) => {
+                pending.push(target as usize);
+                pending.push(next);
+            }
+            Ret | Exit => {}
+            skip if skip.is_skip() => {
+                let skipped = decode_at(rom, next - PROGRAM_START).map_or(2, |(_, len)| len);
+                pending.push(next);
+                pending.push(next + skipped);
+            }
+            _ => pending.push(next),
+        }
+    }
+    code
+}
+
+/// Formats `rom` as a listing of address, raw bytes and mnemonic. Bytes
+/// never reached from `PROGRAM_START` are listed as `DB` data, unless
+/// `trace` is false, in which case everything is decoded as code.
+pub fn listing(rom: &[u8], trace: bool) -> String {
+    let code = if trace { trace_code(rom) } else { vec![true; rom.len()] };
+    let mut text = String::new();
+    let mut offset = 0;
+
+    while offset < rom.len() {
+        let addr = PROGRAM_START + offset;
+        let decoded = if code[offset] { decode_at(rom, offset) } else { None };
+
+        let (bytes, mnemonic) = match decoded {
+            Some((instruction, len)) => (&rom[offset..offset + len], instruction.to_string()),
+            None => {
+                // group up to 4 consecutive data bytes per line
+                let mut end = offset + 1;
+                while end < rom.len() && end - offset < 4 && !code[end] {
+                    end += 1;
+                }
+                let bytes = &rom[offset..end];
+                let values: Vec<String> = bytes.iter().map(|byte| format!("0x{:02X}", byte)).collect();
+                (bytes, format!("DB {}", values.join(", ")))
+            }
+        };
+
+        let raw: Vec<String> = bytes.iter().map(|byte| format!("{:02X}", byte)).collect();
+        let _ = writeln!(text, "{:04X}  {:<11}  {}", addr, raw.join(" "), mnemonic);
+        offset += bytes.len();
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::Instruction::*;
+
+    #[test]
+    fn disassembles_superchip_and_xochip_opcodes() {
+        let cases = [
+            (0x00C5, ScdN(5), "SCD 5"),
+            (0x00D2, ScuN(2), "SCU 2"),
+            (0x00FB, Scr, "SCR"),
+            (0x00FD, Exit, "EXIT"),
+            (0x00FF, High, "HIGH"),
+            (0xD120, DrwVxVyN(1, 2, 0), "DRW V1, V2, 0"),
+            (0xF330, LdHfVx(3), "LD HF, V3"),
+            (0xF775, LdRVx(7), "LD R, V7"),
+            (0xF785, LdVxR(7), "LD V7, R"),
+            (0x5122, SaveVxVy(1, 2), "SAVE V1, V2"),
+            (0x5123, LoadVxVy(1, 2), "LOAD V1, V2"),
+            (0xF201, PlaneN(2), "PLANE 2"),
+            (0xF002, Audio, "AUDIO"),
+            (0xF43A, PitchVx(4), "PITCH V4"),
+            (0x9AB0, SneVxVy(0xA, 0xB), "SNE VA, VB"),
+        ];
+        for &(opcode, instruction, text) in &cases {
+            assert_eq!(disassemble(opcode), instruction, "{:04X}", opcode);
+            assert_eq!(instruction.to_string(), text);
+        }
+    }
+
+    #[test]
+    fn rejects_the_variants_the_machine_rejects() {
+        for &opcode in &[0x9AB1, 0x5121, 0x812F, 0xE1A2, 0xF102, 0xF100, 0x0123] {
+            assert_eq!(disassemble(opcode), Unknown(opcode));
+        }
+        assert_eq!(Unknown(0x9AB1).to_string(), "DW 0x9AB1");
+    }
+
+    #[test]
+    fn decodes_the_long_load_with_its_address() {
+        let memory = [0xF0, 0x00, 0x12, 0x34, 0x00, 0xE0, 0xF0, 0x00, 0x12];
+        assert_eq!(disassemble(0xF000), LdILong(0));
+        assert_eq!(decode_at(&memory, 0), Some((LdILong(0x1234), 4)));
+        assert_eq!(LdILong(0x1234).to_string(), "LD I, LONG 0x1234");
+        assert_eq!(decode_at(&memory, 4), Some((Cls, 2)));
+        // the address is cut off by the end of memory
+        assert_eq!(decode_at(&memory, 6), None);
+        assert_eq!(decode_at(&memory, 8), None);
+    }
+
+    /// JP over four data bytes to a long load of the sprite after EXIT
+    const ROM: [u8; 14] = [
+        0x12, 0x06,
+        0xAB, 0xCD, 0x01, 0x23,
+        0xF0, 0x00, 0x02, 0x0C,
+        0x00, 0xFD,
+        0xFF, 0x81,
+    ];
+
+    #[test]
+    fn traces_code_through_jumps() {
+        let code = trace_code(&ROM);
+        let expected = [true, true, false, false, false, false, true, true, true, true, true, true, false, false];
+        assert_eq!(code, expected);
+    }
+
+    #[test]
+    fn traces_both_outcomes_of_a_skip_over_a_long_load() {
+        // SE V0, 0 skips the whole 4-byte F000 NNNN to land on JP 0x20A
+        let rom = [0x30, 0x00, 0xF0, 0x00, 0x02, 0x00, 0x12, 0x0A, 0xFF, 0xFF, 0x00, 0xFD];
+        let code = trace_code(&rom);
+        assert_eq!(code, [true, true, true, true, true, true, true, true, false, false, true, true]);
+    }
+
+    #[test]
+    fn lists_code_and_data() {
+        assert_eq!(listing(&ROM, true), "\
+0200  12 06        JP 0x206
+0202  AB CD 01 23  DB 0xAB, 0xCD, 0x01, 0x23
+0206  F0 00 02 0C  LD I, LONG 0x020C
+020A  00 FD        EXIT
+020C  FF 81        DB 0xFF, 0x81
+");
+        assert_eq!(listing(&ROM, false), "\
+0200  12 06        JP 0x206
+0202  AB CD        LD I, 0xBCD
+0204  01 23        DW 0x0123
+0206  F0 00 02 0C  LD I, LONG 0x020C
+020A  00 FD        EXIT
+020C  FF 81        DW 0xFF81
+");
+    }
+}
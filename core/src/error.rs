@@ -0,0 +1,52 @@
+use std::error::Error;
+use std::fmt;
+
+/// Faults raised by the virtual machine while executing a ROM
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VmError {
+    /// The fetched opcode doesn't decode to any known instruction
+    UnknownOpcode { pc: usize, opcode: u16 },
+
+    /// `2NNN` was executed with all stack slots in use
+    StackOverflow,
+
+    /// `00EE` was executed with an empty stack
+    StackUnderflow,
+
+    /// An instruction tried to access memory outside of the RAM
+    MemoryOutOfBounds { addr: usize },
+
+    /// `EX9E` / `EXA1` referenced a key outside of the keypad
+    InvalidKey { key: u8 },
+}
+
+/// Result of a successfully executed cycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CycleOutcome {
+    /// The instruction was executed
+    Executed,
+
+    /// `FX0A` is blocked until a key is pressed
+    WaitingForKey,
+
+    /// `00FD` asked the interpreter to exit
+    Exit,
+}
+
+impl fmt::Display for VmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            VmError::UnknownOpcode { pc, opcode } => {
+                write!(f, "unknown opcode 0x{:04X} at 0x{:03X}", opcode, pc)
+            }
+            VmError::StackOverflow => write!(f, "stack overflow"),
+            VmError::StackUnderflow => write!(f, "return with an empty stack"),
+            VmError::MemoryOutOfBounds { addr } => {
+                write!(f, "memory access out of bounds at 0x{:X}", addr)
+            }
+            VmError::InvalidKey { key } => write!(f, "invalid key 0x{:X}", key),
+        }
+    }
+}
+
+impl Error for VmError {}
@@ -0,0 +1,150 @@
+use crate::palette::{Palette, Rgb};
+use crate::vm::{Vm, PLANE_1, PLANE_2};
+
+/// Names of the modes accepted by `FilterMode::from_name`
+pub const FILTER_MODES: [&str; 3] = ["off", "decay", "two-frame"];
+
+/// Share of its intensity a pixel keeps per frame after going dark,
+/// in percent, unless configured otherwise
+pub const DEFAULT_DECAY: u32 = 50;
+
+/// Intensities below this are rounded down to a dark pixel
+const DARK: f32 = 0.01;
+
+/// Display filter hiding the flicker of sprites moved by erasing and
+/// redrawing them
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum FilterMode {
+    /// Pixels as they are on the screen
+    #[default]
+    Off,
+    /// Pixels light up at once and fade out like phosphor, keeping
+    /// the given share of their intensity each frame
+    Decay(f32),
+    /// Pixels are lit when they were lit in either of the last two frames
+    TwoFrame,
+}
+
+impl FilterMode {
+    /// Looks up a mode by its name, see `FILTER_MODES`; `decay` is the
+    /// percentage used by the `decay` mode
+    pub fn from_name(name: &str, decay: u32) -> Option<FilterMode> {
+        match name {
+            "off" => Some(FilterMode::Off),
+            "decay" => Some(FilterMode::Decay(decay.min(100) as f32 / 100.0)),
+            "two-frame" => Some(FilterMode::TwoFrame),
+            _ => None,
+        }
+    }
+}
+
+/// Turns the screen into colours, applying a `FilterMode` over the
+/// frames it is given
+#[derive(Debug, Clone)]
+pub struct Filter {
+    mode: FilterMode,
+    width: usize,
+    height: usize,
+    /// Intensity of the two planes of every pixel, from 0 to 1
+    intensity: Vec<[f32; 2]>,
+    /// Screen of the previous frame
+    previous: Vec<u8>,
+    /// Colours of the last rendered frame, row after row
+    output: Vec<Rgb>,
+    settled: bool,
+}
+
+impl Filter {
+    pub fn new(mode: FilterMode) -> Filter {
+        Filter {
+            mode,
+            width: 0,
+            height: 0,
+            intensity: Vec::new(),
+            previous: Vec::new(),
+            output: Vec::new(),
+            settled: true,
+        }
+    }
+
+    pub fn mode(&self) -> FilterMode {
+        self.mode
+    }
+
+    /// Whether rendering the same screen again would give the same
+    /// colours; while false, the frontend should keep rendering every
+    /// frame even if the machine didn't draw
+    pub fn is_settled(&self) -> bool {
+        self.settled
+    }
+
+    /// Renders one frame of the screen, returning the colours of its
+    /// `Vm::screen_width` by `Vm::screen_height` pixels row after row
+    pub fn render(&mut self, vm: &Vm, palette: &Palette) -> &[Rgb] {
+        let (width, height) = (vm.screen_width(), vm.screen_height());
+        let screen = &vm.screen[..width * height];
+
+        // nothing to fade from after switching display modes
+        if (width, height) != (self.width, self.height) {
+            self.width = width;
+            self.height = height;
+            self.intensity = screen.iter().map(|&pixel| planes(pixel)).collect();
+            self.previous = screen.to_vec();
+        }
+
+        self.output.clear();
+        self.settled = true;
+        match self.mode {
+            FilterMode::Off => {
+                self.output.extend(screen.iter().map(|&pixel| palette.color(pixel)));
+            }
+            FilterMode::Decay(decay) => {
+                for (intensity, &pixel) in self.intensity.iter_mut().zip(screen) {
+                    let lit = planes(pixel);
+                    for plane in 0..2 {
+                        intensity[plane] = if lit[plane] > 0.0 {
+                            1.0
+                        } else if intensity[plane] * decay < DARK {
+                            0.0
+                        } else {
+                            intensity[plane] * decay
+                        };
+                        self.settled &= intensity[plane] == lit[plane];
+                    }
+                    self.output.push(blend(palette, *intensity));
+                }
+            }
+            FilterMode::TwoFrame => {
+                for (&previous, &pixel) in self.previous.iter().zip(screen) {
+                    self.output.push(palette.color(previous | pixel));
+                    self.settled &= previous == pixel;
+                }
+            }
+        }
+        self.previous.copy_from_slice(screen);
+        &self.output
+    }
+}
+
+/// Intensities of the planes of a pixel, 0 or 1
+fn planes(pixel: u8) -> [f32; 2] {
+    [(pixel & PLANE_1 != 0) as u8 as f32, (pixel & PLANE_2 != 0) as u8 as f32]
+}
+
+/// Mixes the colours of the plane combinations by the intensities of
+/// the two planes
+fn blend(palette: &Palette, [first, second]: [f32; 2]) -> Rgb {
+    let weights = [
+        (1.0 - first) * (1.0 - second),
+        first * (1.0 - second),
+        (1.0 - first) * second,
+        first * second,
+    ];
+    let channel = |component: fn(Rgb) -> u8| {
+        let value: f32 = palette.colors.iter().zip(&weights)
+            .map(|(&color, weight)| component(color) as f32 * weight)
+            .sum();
+        value.round() as u8
+    };
+    Rgb(channel(|color| color.0), channel(|color| color.1), channel(|color| color.2))
+}
@@ -0,0 +1,87 @@
+use std::io::{self, Write};
+
+use crate::palette::Palette;
+use crate::vm::Vm;
+
+/// Copy of the active area of `Vm::screen`
+///
+/// Unlike the screen buffer, which is sized for the high resolution mode,
+/// a frame holds exactly `width * height` pixels, so it can be compared,
+/// stored and encoded without looking at the display mode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub width: usize,
+    pub height: usize,
+    /// Bit masks of planes, row after row
+    pub pixels: Vec<u8>,
+}
+
+impl Frame {
+    /// Takes a copy of the screen in its current display mode
+    pub fn capture(vm: &Vm) -> Frame {
+        let width = vm.screen_width();
+        let height = vm.screen_height();
+
+        Frame {
+            width,
+            height,
+            pixels: vm.screen[..width * height].to_vec(),
+        }
+    }
+
+    /// Renders the frame as text, one line per row: `.` for blank pixels,
+    /// `#` for pixels lit in the first plane only, `2` for the second
+    /// plane only and `3` for both XO-CHIP planes
+    pub fn to_ascii(&self) -> String {
+        let mut text = String::with_capacity((self.width + 1) * self.height);
+        for row in self.pixels.chunks(self.width) {
+            for &pixel in row {
+                text.push(match pixel {
+                    0 => '.',
+                    1 => '#',
+                    2 => '2',
+                    _ => '3',
+                });
+            }
+            text.push('\n');
+        }
+        text
+    }
+
+    /// Converts the frame to RGB triplets, every pixel becoming a
+    /// `scale` x `scale` square
+    pub fn to_rgb(&self, palette: &Palette, scale: usize) -> Vec<u8> {
+        let mut rgb = Vec::with_capacity(self.pixels.len() * scale * scale * 3);
+        for row in self.pixels.chunks(self.width) {
+            for _ in 0..scale {
+                for &pixel in row {
+                    let color = palette.color(pixel);
+                    for _ in 0..scale {
+                        rgb.extend_from_slice(&[color.0, color.1, color.2]);
+                    }
+                }
+            }
+        }
+        rgb
+    }
+
+    /// Encodes the frame as a PNG image
+    pub fn write_png<W: Write>(&self, writer: W, palette: &Palette, scale: usize) -> io::Result<()> {
+        let width = (self.width * scale) as u32;
+        let height = (self.height * scale) as u32;
+
+        let mut encoder = png::Encoder::new(writer, width, height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(to_io_error)?;
+        writer.write_image_data(&self.to_rgb(palette, scale)).map_err(to_io_error)?;
+        writer.finish().map_err(to_io_error)
+    }
+}
+
+pub(crate) fn to_io_error(why: png::EncodingError) -> io::Error {
+    match why {
+        png::EncodingError::IoError(why) => why,
+        why => io::Error::new(io::ErrorKind::InvalidData, why),
+    }
+}
@@ -0,0 +1,31 @@
+use crate::audio::Sound;
+use crate::vm::Vm;
+
+/// Output devices driven by the virtual machine
+///
+/// Every frontend (the SDL window, headless runners, tests) implements this
+/// trait. The machine calls the sound methods from its timers; `draw` is
+/// called by the frontend's own loop whenever `Vm::draw_flag` is set.
+pub trait Frontend {
+    /// Starts the buzzer, or updates the sound it plays.
+    /// Called on every timer tick while the sound timer is running.
+    fn play_sound(&mut self, sound: &Sound);
+
+    /// Stops the buzzer
+    fn stop_sound(&mut self);
+
+    /// Presents the current content of `Vm::screen`
+    fn draw(&mut self, vm: &Vm);
+}
+
+/// Frontend that discards all output
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NullFrontend;
+
+impl Frontend for NullFrontend {
+    fn play_sound(&mut self, _sound: &Sound) {}
+
+    fn stop_sound(&mut self) {}
+
+    fn draw(&mut self, _vm: &Vm) {}
+}
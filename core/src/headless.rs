@@ -0,0 +1,209 @@
+use std::error::Error;
+use std::fmt;
+use std::fmt::Write;
+
+use crate::error::{CycleOutcome, VmError};
+use crate::frontend::Frontend;
+use crate::trace::Tracer;
+use crate::vm::{Vm, KEYS_COUNT};
+
+/// How long a headless run lasts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    Cycles(u64),
+    Frames(u64),
+}
+
+impl Limit {
+    /// Number of cycles to execute at `cycles_per_frame` instructions per frame
+    pub fn cycles(self, cycles_per_frame: usize) -> u64 {
+        match self {
+            Limit::Cycles(cycles) => cycles,
+            Limit::Frames(frames) => frames * cycles_per_frame as u64,
+        }
+    }
+}
+
+/// Press or release of a key at the start of a frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyEvent {
+    pub frame: u64,
+    pub key: u8,
+    pub pressed: bool,
+}
+
+/// Scripted key input, read from a text file with one event per line:
+///
+/// ```text
+/// # frame  action  key
+/// 10       down    5
+/// 14       up      5
+/// ```
+///
+/// Keys are the hex digits of the keypad. Empty lines and lines starting
+/// with `#` are ignored.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KeyScript {
+    events: Vec<KeyEvent>,
+}
+
+/// Error for key scripts that couldn't be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for ScriptError {}
+
+impl KeyScript {
+    pub fn parse(text: &str) -> Result<KeyScript, ScriptError> {
+        let mut events = Vec::new();
+
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |message: String| ScriptError { line: index + 1, message };
+
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() != 3 {
+                return Err(error("expected '<frame> <down|up> <key>'".to_string()));
+            }
+            let frame = fields[0].parse::<u64>()
+                .map_err(|_| error(format!("invalid frame '{}'", fields[0])))?;
+            let pressed = match fields[1] {
+                "down" => true,
+                "up" => false,
+                action => return Err(error(format!("invalid action '{}'", action))),
+            };
+            let key = u8::from_str_radix(fields[2], 16).ok()
+                .filter(|&key| (key as usize) < KEYS_COUNT)
+                .ok_or_else(|| error(format!("invalid key '{}'", fields[2])))?;
+
+            events.push(KeyEvent { frame, key, pressed });
+        }
+
+        // keep the order of the file for events happening on the same frame
+        events.sort_by_key(|event| event.frame);
+        Ok(KeyScript { events })
+    }
+
+    /// Updates `Vm::key_states` with the events of `frame`
+    pub fn apply(&self, frame: u64, vm: &mut Vm) {
+        for event in self.events.iter().filter(|event| event.frame == frame) {
+            vm.key_states[event.key as usize] = event.pressed;
+        }
+    }
+}
+
+/// How a headless run ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunStatus {
+    /// The cycle or frame limit was reached
+    Completed,
+    /// The ROM executed `00FD`
+    Exited,
+    /// The machine faulted
+    Fault(VmError),
+}
+
+impl RunStatus {
+    pub fn name(&self) -> &'static str {
+        match self {
+            RunStatus::Completed => "completed",
+            RunStatus::Exited => "exited",
+            RunStatus::Fault(_) => "fault",
+        }
+    }
+}
+
+/// Summary of a headless run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RunReport {
+    pub status: RunStatus,
+    pub cycles: u64,
+}
+
+/// Runs the machine until `limit` is reached, the ROM exits or the
+/// machine faults, feeding it the scripted key input and logging every
+/// instruction to `tracer`
+pub fn run<F: Frontend>(vm: &mut Vm, limit: Limit, cycles_per_frame: usize, script: &KeyScript,
+                        frontend: &mut F, tracer: Option<&mut Tracer>) -> RunReport {
+    match tracer {
+        Some(tracer) => run_with(vm, limit, cycles_per_frame, script, frontend, |vm, _| tracer.record(vm), |_| {}),
+        None => run_with(vm, limit, cycles_per_frame, script, frontend, |_, _| {}, |_| {}),
+    }
+}
+
+/// Same as `run`, calling `before_cycle` with the cycle number ahead of
+/// every instruction, and `after_frame` at the end of every 60 Hz frame
+/// once the timers have ticked
+pub fn run_with<F, B, A>(vm: &mut Vm, limit: Limit, cycles_per_frame: usize, script: &KeyScript,
+                         frontend: &mut F, mut before_cycle: B, mut after_frame: A) -> RunReport
+    where F: Frontend, B: FnMut(&Vm, u64), A: FnMut(&Vm) {
+    let cycles_per_frame = cycles_per_frame.max(1);
+    let total = limit.cycles(cycles_per_frame);
+    let mut cycles = 0;
+    let mut frame = 0;
+
+    while cycles < total {
+        script.apply(frame, vm);
+        frame += 1;
+
+        let count = (total - cycles).min(cycles_per_frame as u64) as usize;
+        let before_cycle = |vm: &Vm| {
+            before_cycle(vm, cycles);
+            cycles += 1;
+        };
+        // a last partial frame stops before the timers tick
+        let outcome = if count == cycles_per_frame {
+            vm.run_frame_with(count, frontend, before_cycle)
+        } else {
+            vm.run_cycles_with(count, before_cycle)
+        };
+        match outcome {
+            Ok(CycleOutcome::Exit) => return RunReport { status: RunStatus::Exited, cycles },
+            Ok(_) => {}
+            Err(error) => return RunReport { status: RunStatus::Fault(error), cycles },
+        }
+        if count == cycles_per_frame {
+            after_frame(vm);
+        }
+    }
+
+    RunReport { status: RunStatus::Completed, cycles }
+}
+
+/// Describes the registers and the outcome of a run as a JSON object
+pub fn registers_json(vm: &Vm, report: &RunReport) -> String {
+    let list = |values: &mut dyn Iterator<Item = u32>| {
+        values.map(|value| value.to_string()).collect::<Vec<_>>().join(", ")
+    };
+
+    let mut json = String::new();
+    let _ = writeln!(json, "{{");
+    let _ = writeln!(json, "  \"status\": \"{}\",", report.status.name());
+    if let RunStatus::Fault(error) = report.status {
+        let _ = writeln!(json, "  \"error\": \"{}\",", error);
+    }
+    let _ = writeln!(json, "  \"cycles\": {},", report.cycles);
+    let _ = writeln!(json, "  \"pc\": {},", vm.pc);
+    let _ = writeln!(json, "  \"opcode\": {},", vm.opcode);
+    let _ = writeln!(json, "  \"i\": {},", vm.i);
+    let _ = writeln!(json, "  \"v\": [{}],", list(&mut vm.v.iter().map(|&v| v as u32)));
+    let _ = writeln!(json, "  \"sp\": {},", vm.sp);
+    let _ = writeln!(json, "  \"stack\": [{}],", list(&mut vm.stack[..vm.sp].iter().map(|&addr| addr as u32)));
+    let _ = writeln!(json, "  \"delay_timer\": {},", vm.delay_timer);
+    let _ = writeln!(json, "  \"sound_timer\": {},", vm.sound_timer);
+    let _ = writeln!(json, "  \"hires\": {}", vm.hires);
+    let _ = writeln!(json, "}}");
+    json
+}
@@ -0,0 +1,40 @@
+//! Core of the **chip-8** interpreter
+//!
+//! Holds the virtual machine, the opcode handlers, the built-in font and
+//! the ROM loader. Nothing in here depends on SDL: frontends hook into the
+//! machine through the `Frontend` trait and read `Vm::screen` directly.
+
+extern crate rand;
+
+pub mod asm;
+pub mod audio;
+pub mod cli;
+pub mod clock;
+pub mod debugger;
+pub mod disasm;
+pub mod error;
+pub mod filter;
+pub mod font;
+pub mod frame;
+pub mod frontend;
+pub mod headless;
+pub mod loader;
+pub mod opcodes;
+pub mod palette;
+pub mod quirks;
+pub mod record;
+pub mod rewind;
+pub mod state;
+pub mod trace;
+pub mod vm;
+
+pub use crate::error::{CycleOutcome, VmError};
+pub use crate::filter::{Filter, FilterMode};
+pub use crate::frame::Frame;
+pub use crate::frontend::{Frontend, NullFrontend};
+pub use crate::loader::LoadError;
+pub use crate::palette::Palette;
+pub use crate::quirks::Quirks;
+pub use crate::record::Recorder;
+pub use crate::state::StateError;
+pub use crate::vm::Vm;
@@ -0,0 +1,49 @@
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+/// Errors raised while loading a ROM
+#[derive(Debug)]
+pub enum LoadError {
+    /// The ROM file couldn't be opened or read
+    Io(io::Error),
+
+    /// The ROM doesn't fit into the program area of the RAM
+    TooLarge { size: usize, max: usize },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Io(why) => write!(f, "{}", why),
+            LoadError::TooLarge { size, max } => {
+                write!(f, "ROM is {} bytes, at most {} bytes fit into RAM", size, max)
+            }
+        }
+    }
+}
+
+impl Error for LoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LoadError::Io(why) => Some(why),
+            LoadError::TooLarge { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for LoadError {
+    fn from(why: io::Error) -> LoadError {
+        LoadError::Io(why)
+    }
+}
+
+/// Reads the whole ROM file at `path`
+pub fn read_rom<P: AsRef<Path>>(path: P) -> Result<Vec<u8>, LoadError> {
+    let mut file = File::open(path)?;
+    let mut buffer = Vec::new();
+    file.read_to_end(&mut buffer)?;
+    Ok(buffer)
+}
@@ -0,0 +1,709 @@
+extern crate rand;
+
+use crate::audio::PATTERN_BYTES;
+use crate::error::{CycleOutcome, VmError};
+use crate::font::{BIG_FONT_HEIGHT, BIG_FONT_START, FONT_HEIGHT, FONT_START};
+use crate::vm::{Vm, KEYS_COUNT, PLANE_1, PLANE_2, RPL_FLAGS_COUNT, SCREEN_PIXELS, STACK_SIZE};
+use rand::Rng;
+
+// Skips the instruction following the current one. The XO-CHIP
+// long load F000 NNNN is 4 bytes long, so it is skipped as a whole.
+fn skip_next(vm: &mut Vm) {
+    let next = vm.pc + 2;
+    let long_load = vm.ram.get(next) == Some(&0xF0) && vm.ram.get(next + 1) == Some(&0x00);
+
+    vm.pc += if long_load { 6 } else { 4 };
+}
+
+// Moves the content of the selected planes by (dx, dy) pixels,
+// filling the uncovered area with blank pixels.
+fn scroll(vm: &mut Vm, dx: isize, dy: isize) {
+    let width = vm.screen_width() as isize;
+    let height = vm.screen_height() as isize;
+    let planes = vm.planes;
+    let source = vm.screen;
+
+    for y in 0..height {
+        for x in 0..width {
+            let (from_x, from_y) = (x - dx, y - dy);
+            let inside = from_x >= 0 && from_x < width && from_y >= 0 && from_y < height;
+            let moved = if inside {
+                source[(from_x + from_y * width) as usize] & planes
+            } else {
+                0
+            };
+            let pixel = &mut vm.screen[(x + y * width) as usize];
+            *pixel = (*pixel & !planes) | moved;
+        }
+    }
+    vm.draw_flag = true;
+}
+
+// 00CN =
+// Scroll the display down by N pixels. (SUPER-CHIP)
+pub fn scd_n(vm: &mut Vm) {
+    let n = (vm.opcode & 0x000F) as isize;
+    scroll(vm, 0, n);
+    vm.pc += 2;
+}
+
+// 00DN =
+// Scroll the display up by N pixels. (XO-CHIP)
+pub fn scu_n(vm: &mut Vm) {
+    let n = (vm.opcode & 0x000F) as isize;
+    scroll(vm, 0, -n);
+    vm.pc += 2;
+}
+
+// 00E0 =
+// Clear the selected planes of the screen.
+pub fn cls(vm: &mut Vm) {
+    let planes = vm.planes;
+    for pixel in vm.screen.iter_mut() {
+        *pixel &= !planes;
+    }
+    vm.draw_flag = true;
+    vm.pc += 2;
+}
+
+// 00EE =
+// Return from subroutine.
+pub fn ret(vm: &mut Vm) -> Result<(), VmError> {
+    if vm.sp == 0 {
+        return Err(VmError::StackUnderflow);
+    }
+    vm.sp -= 1;
+    vm.pc = vm.stack[vm.sp] as usize;
+    vm.pc += 2;
+    Ok(())
+}
+
+// 00FB =
+// Scroll the display right by 4 pixels. (SUPER-CHIP)
+pub fn scr(vm: &mut Vm) {
+    scroll(vm, 4, 0);
+    vm.pc += 2;
+}
+
+// 00FC =
+// Scroll the display left by 4 pixels. (SUPER-CHIP)
+pub fn scl(vm: &mut Vm) {
+    scroll(vm, -4, 0);
+    vm.pc += 2;
+}
+
+// 00FD =
+// Exit the interpreter. (SUPER-CHIP)
+pub fn exit(_vm: &mut Vm) -> CycleOutcome {
+    CycleOutcome::Exit
+}
+
+// 00FE =
+// Switch to the 64 x 32 low resolution mode and clear the screen. (SUPER-CHIP)
+pub fn low(vm: &mut Vm) {
+    vm.hires = false;
+    vm.screen = [0; SCREEN_PIXELS];
+    vm.draw_flag = true;
+    vm.pc += 2;
+}
+
+// 00FF =
+// Switch to the 128 x 64 high resolution mode and clear the screen. (SUPER-CHIP)
+pub fn high(vm: &mut Vm) {
+    vm.hires = true;
+    vm.screen = [0; SCREEN_PIXELS];
+    vm.draw_flag = true;
+    vm.pc += 2;
+}
+
+// 1NNN =
+// Jump to address NNN.
+pub fn jp_addr(vm: &mut Vm) {
+    vm.pc = (vm.opcode & 0x0FFF) as usize;
+}
+
+// 2NNN =
+// Call subroutine at NNN.
+pub fn call_addr(vm: &mut Vm) -> Result<(), VmError> {
+    if vm.sp == STACK_SIZE {
+        return Err(VmError::StackOverflow);
+    }
+    vm.stack[vm.sp] = (vm.pc) as u16;
+    vm.sp += 1;
+    vm.pc = (vm.opcode & 0x0FFF) as usize;
+    Ok(())
+}
+
+// 3XNN =
+// Skip the next instruction if VX equals NN.
+pub fn se_vx_byte(vm: &mut Vm) {
+    let x = ((vm.opcode & 0x0F00) >> 8) as usize;
+    let vx = vm.v[x];
+
+    if vx == (vm.opcode & 0x00FF) as u8 {
+        skip_next(vm);
+    } else {
+        vm.pc += 2;
+    }
+}
+
+// 4XNN =
+// Skip the next instruction if VX isn't equal to NN.
+pub fn sne_vx_byte(vm: &mut Vm) {
+    let x = ((vm.opcode & 0x0F00) >> 8) as usize;
+    let vx = vm.v[x];
+
+    if vx != (vm.opcode & 0x00FF) as u8 {
+        skip_next(vm);
+    } else {
+        vm.pc += 2;
+    }
+}
+
+// 5XY0 =
+// Skip the next instruction if VX equals VY.
+pub fn se_vx_vy(vm: &mut Vm) {
+    let x = ((vm.opcode & 0x0F00) >> 8) as usize;
+    let vx = vm.v[x];
+    let y = ((vm.opcode & 0x00F0) >> 4) as usize;
+    let vy = vm.v[y];
+
+    if vx == vy {
+        skip_next(vm);
+    } else {
+        vm.pc += 2;
+    }
+}
+
+// 5XY2 =
+// Store values contained in VX-VY in memory starting at
+// address I, without changing I. X may be greater than Y. (XO-CHIP)
+pub fn save_vx_vy(vm: &mut Vm) -> Result<(), VmError> {
+    let x = ((vm.opcode & 0x0F00) >> 8) as usize;
+    let y = ((vm.opcode & 0x00F0) >> 4) as usize;
+    let count = if x <= y { y - x + 1 } else { x - y + 1 };
+
+    vm.check_ram(vm.i as usize, count)?;
+    for offset in 0..count {
+        let register = if x <= y { x + offset } else { x - offset };
+        vm.ram[vm.i as usize + offset] = vm.v[register];
+    }
+    vm.last_write = Some((vm.i as usize, count));
+    vm.pc += 2;
+    Ok(())
+}
+
+// 5XY3 =
+// Fills VX-VY with values from memory starting at
+// address I, without changing I. X may be greater than Y. (XO-CHIP)
+pub fn load_vx_vy(vm: &mut Vm) -> Result<(), VmError> {
+    let x = ((vm.opcode & 0x0F00) >> 8) as usize;
+    let y = ((vm.opcode & 0x00F0) >> 4) as usize;
+    let count = if x <= y { y - x + 1 } else { x - y + 1 };
+
+    vm.check_ram(vm.i as usize, count)?;
+    for offset in 0..count {
+        let register = if x <= y { x + offset } else { x - offset };
+        vm.v[register] = vm.ram[vm.i as usize + offset];
+    }
+    vm.pc += 2;
+    Ok(())
+}
+
+// 6XNN =
+// Set VX to NN.
+pub fn ld_vx_byte(vm: &mut Vm) {
+    vm.v[((vm.opcode & 0x0F00) >> 8) as usize] = (vm.opcode & 0x00FF) as u8;
+    vm.pc += 2;
+}
+
+// 7XNN =
+// Add NN to VX.
+pub fn add_vx_byte(vm: &mut Vm) {
+    let x = ((vm.opcode & 0x0F00) >> 8) as usize;
+    vm.v[x] = vm.v[x].wrapping_add((vm.opcode & 0x00FF) as u8);
+
+    vm.pc += 2;
+}
+
+// 8XY0 =
+// Set VX to the value of VY
+pub fn ld_vx_vy(vm: &mut Vm) {
+    let x = ((vm.opcode & 0x0F00) >> 8) as usize;
+    let y = ((vm.opcode & 0x00F0) >> 4) as usize;
+
+    vm.v[x] = vm.v[y];
+    vm.pc += 2;
+}
+
+// 8XY1 =
+// Set VX to VX or VY.
+pub fn or_vx_vy(vm: &mut Vm) {
+    let x = ((vm.opcode & 0x0F00) >> 8) as usize;
+    let vx = vm.v[x];
+    let y = ((vm.opcode & 0x00F0) >> 4) as usize;
+    let vy = vm.v[y];
+
+    vm.v[x] = vx | vy;
+    if vm.quirks.logic_resets_vf {
+        vm.v[0xF] = 0;
+    }
+    vm.pc += 2;
+}
+
+// 8XY2 =
+// Set VX to VX and VY.
+pub fn and_vx_vy(vm: &mut Vm) {
+    let x = ((vm.opcode & 0x0F00) >> 8) as usize;
+    let vx = vm.v[x];
+    let y = ((vm.opcode & 0x00F0) >> 4) as usize;
+    let vy = vm.v[y];
+
+    vm.v[x] = vx & vy;
+    if vm.quirks.logic_resets_vf {
+        vm.v[0xF] = 0;
+    }
+    vm.pc += 2;
+}
+
+// 8XY3 =
+// Set VX to VX xor VY.
+pub fn xor_vx_vy(vm: &mut Vm) {
+    let x = ((vm.opcode & 0x0F00) >> 8) as usize;
+    let vx = vm.v[x];
+    let y = ((vm.opcode & 0x00F0) >> 4) as usize;
+    let vy = vm.v[y];
+
+    vm.v[x] = vx ^ vy;
+    if vm.quirks.logic_resets_vf {
+        vm.v[0xF] = 0;
+    }
+    vm.pc += 2;
+}
+
+// 8XY4 =
+// Adds VY to VX. VF is set to 1 when there's a carry,
+// and to 0 when there isn't.
+pub fn add_vx_vy(vm: &mut Vm) {
+    let x = ((vm.opcode & 0x0F00) >> 8) as usize;
+    let vx = vm.v[x];
+    let y = ((vm.opcode & 0x00F0) >> 4) as usize;
+    let vy = vm.v[y];
+
+    // the flag is written last, so it wins when VF is the destination
+    let (result, carry) = vx.overflowing_add(vy);
+    vm.v[x] = result;
+    vm.v[0xF] = carry as u8;
+    vm.pc += 2;
+}
+
+// 8XY5 =
+// Subtracts VY from VX. VF is set to 1 when there's no borrow,
+// and to 0 when there is. Result is stored in VX.
+pub fn sub_vx_vy(vm: &mut Vm) {
+    let x = ((vm.opcode & 0x0F00) >> 8) as usize;
+    let vx = vm.v[x];
+    let y = ((vm.opcode & 0x00F0) >> 4) as usize;
+    let vy = vm.v[y];
+
+    vm.v[x] = vx.wrapping_sub(vy);
+    vm.v[0xF] = (vx >= vy) as u8;
+    vm.pc += 2;
+}
+
+// 8XY6 =
+// Shifts VX right by one. VF is set to the value of
+// the least significant bit of VX before the shift.
+// With the `shift_uses_vy` quirk VY is shifted into VX instead.
+pub fn shr_vx_vy(vm: &mut Vm) {
+    let x = ((vm.opcode & 0x0F00) >> 8) as usize;
+    let y = ((vm.opcode & 0x00F0) >> 4) as usize;
+    let source = if vm.quirks.shift_uses_vy { vm.v[y] } else { vm.v[x] };
+
+    vm.v[x] = source >> 1;
+    vm.v[0xF] = source & 0x01;
+    vm.pc += 2;
+}
+
+// 8XY7 =
+// Subtracts VX from VY. VF is set to 1 when there's no borrow,
+// and to 0 when there is. Result is stored in VX.
+pub fn subn_vx_vy(vm: &mut Vm) {
+    let x = ((vm.opcode & 0x0F00) >> 8) as usize;
+    let vx = vm.v[x];
+    let y = ((vm.opcode & 0x00F0) >> 4) as usize;
+    let vy = vm.v[y];
+
+    vm.v[x] = vy.wrapping_sub(vx);
+    vm.v[0xF] = (vy >= vx) as u8;
+    vm.pc += 2;
+}
+
+// 8XYE =
+// Shifts VX left by one. VF is set to the value of
+// the most significant bit of VX before the shift.
+// With the `shift_uses_vy` quirk VY is shifted into VX instead.
+pub fn shl_vx_vy(vm: &mut Vm) {
+    let x = ((vm.opcode & 0x0F00) >> 8) as usize;
+    let y = ((vm.opcode & 0x00F0) >> 4) as usize;
+    let source = if vm.quirks.shift_uses_vy { vm.v[y] } else { vm.v[x] };
+
+    vm.v[x] = source << 1;
+    vm.v[0xF] = (source & 0x80) >> 7;
+    vm.pc += 2;
+}
+
+// 9XY0 =
+// Skip the next instruction if VX isn't equal to VY.
+pub fn sne_vx_vy(vm: &mut Vm) {
+    let x = ((vm.opcode & 0x0F00) >> 8) as usize;
+    let vx = vm.v[x];
+    let y = ((vm.opcode & 0x00F0) >> 4) as usize;
+    let vy = vm.v[y];
+
+    if vx != vy {
+        skip_next(vm);
+    } else {
+        vm.pc += 2;
+    }
+}
+
+// ANNN =
+// Set I to the address NNN.
+pub fn ld_i_addr(vm: &mut Vm) {
+    vm.i = vm.opcode & 0x0FFF;
+    vm.pc += 2;
+}
+
+// BNNN =
+// Jump to the address NNN plus V0.
+// With the `jump_uses_vx` quirk this is BXNN, jumping to XNN plus VX.
+pub fn jp_v0_addr(vm: &mut Vm) {
+    let x = if vm.quirks.jump_uses_vx { ((vm.opcode & 0x0F00) >> 8) as usize } else { 0x0 };
+    vm.pc = ((vm.opcode & 0x0FFF) as usize) + (vm.v[x] as usize);
+}
+
+// CXNN =
+// Set VX to a random number, masked by NN.
+pub fn rnd_vx_byte(vm: &mut Vm) {
+    let x = ((vm.opcode & 0x0F00) >> 8) as usize;
+    let mask = vm.opcode & 0x00FF;
+    let random_number = vm.rng.gen::<u8>();
+
+    vm.v[x] = random_number & (mask as u8);
+    vm.pc += 2;
+}
+
+// Draws one sprite stored at `address` onto the given plane of the
+// screen, with its top left corner at (x0, y0). Returns whether any
+// lit pixel was turned off.
+fn draw_plane(vm: &mut Vm, plane: u8, address: usize, sprite_width: usize, height: usize, x0: usize, y0: usize) -> bool {
+    let width = vm.screen_width();
+    let screen_height = vm.screen_height();
+    let row_bytes = sprite_width / 8;
+    let mut collision = false;
+
+    for y_line in 0..height {
+
+        // get the row, left-aligned in 16 bits
+        let row_address = address + y_line * row_bytes;
+        let mut pixel = (vm.ram[row_address] as u16) << 8;
+        if row_bytes == 2 {
+            pixel |= vm.ram[row_address + 1] as u16;
+        }
+
+        let mut y = y0 + y_line;
+        if y >= screen_height {
+            if vm.quirks.clip_sprites {
+                break;
+            }
+            y %= screen_height;
+        }
+
+        // for each pixel on this line
+        for x_line in 0..sprite_width {
+            // check if the current pixel will be drawn by AND-ING it to 1 - IOW
+            // check if the pixel is set to 1 (This will scan through the row,
+            // one bit at the time)
+
+            if (pixel & (0x8000 >> x_line)) != 0 {
+                let mut x = x0 + x_line;
+                if x >= width {
+                    if vm.quirks.clip_sprites {
+                        continue;
+                    }
+                    x %= width;
+                }
+                let current_position = x + y * width;
+
+                // since the pixel will be drawn, check the destination location in
+                // gfx for collision (verify if that location is flipped on in this plane)
+
+                if vm.screen[current_position] & plane != 0 {
+                    collision = true;
+                }
+                vm.screen[current_position] ^= plane;
+            }
+        }
+    }
+
+    collision
+}
+
+// DXYN =
+// Display n-byte sprite starting at memory location I at (Vx, Vy),
+// Set VF = collision.
+// DXY0 draws a 16 x 16 sprite made of 32 bytes instead. (SUPER-CHIP)
+// With both planes selected the sprite for the second plane follows
+// the one for the first plane in memory. (XO-CHIP)
+// Parts of the sprite past the screen edges wrap around, or are
+// dropped with the `clip_sprites` quirk.
+pub fn drw_vx_vy_n(vm: &mut Vm) -> Result<(), VmError> {
+    let x = ((vm.opcode & 0x0F00) >> 8) as usize;
+    let vx = vm.v[x];
+    let y = ((vm.opcode & 0x00F0) >> 4) as usize;
+    let vy = vm.v[y];
+    let n = (vm.opcode & 0x000F) as usize;
+    let (sprite_width, height) = if n == 0 { (16, 16) } else { (8, n) };
+    let sprite_bytes = height * sprite_width / 8;
+    let planes = [PLANE_1, PLANE_2];
+    let selected = planes.iter().filter(|&&plane| vm.planes & plane != 0).count();
+
+    vm.check_ram(vm.i as usize, sprite_bytes * selected)?;
+
+    // the starting position always wraps around
+    let x0 = vx as usize % vm.screen_width();
+    let y0 = vy as usize % vm.screen_height();
+
+    let mut collision = false;
+    let mut address = vm.i as usize;
+    for &plane in planes.iter() {
+        if vm.planes & plane != 0 {
+            collision |= draw_plane(vm, plane, address, sprite_width, height, x0, y0);
+            address += sprite_bytes;
+        }
+    }
+    vm.v[0xF] = collision as u8;
+
+    vm.draw_flag = true;
+    vm.pc += 2;
+    Ok(())
+}
+
+// EX9E =
+// Skips the next instruction if the key stored in VX
+// is pressed.
+pub fn skp_vx(vm: &mut Vm) -> Result<(), VmError> {
+    let x = ((vm.opcode & 0x0F00) >> 8) as usize;
+    let key = vm.v[x];
+
+    if key as usize >= KEYS_COUNT {
+        return Err(VmError::InvalidKey { key });
+    }
+    if vm.key_states[key as usize] {
+        skip_next(vm);
+    } else {
+        vm.pc += 2;
+    }
+    Ok(())
+}
+
+// EXA1 =
+// Skip the next instruction if the key stored in VX
+// isn't pressed.
+pub fn sknp_vx(vm: &mut Vm) -> Result<(), VmError> {
+    let x = ((vm.opcode & 0x0F00) >> 8) as usize;
+    let key = vm.v[x];
+
+    if key as usize >= KEYS_COUNT {
+        return Err(VmError::InvalidKey { key });
+    }
+    if !vm.key_states[key as usize] {
+        skip_next(vm);
+    } else {
+        vm.pc += 2;
+    }
+    Ok(())
+}
+
+// F000 NNNN =
+// Set I to the 16-bit address NNNN stored after the opcode. (XO-CHIP)
+pub fn ld_i_long(vm: &mut Vm) -> Result<(), VmError> {
+    vm.check_ram(vm.pc + 2, 2)?;
+
+    vm.i = (vm.ram[vm.pc + 2] as u16) << 8 | vm.ram[vm.pc + 3] as u16;
+    vm.pc += 4;
+    Ok(())
+}
+
+// FN01 =
+// Select the planes drawn to by DXYN, 00E0 and the scroll
+// instructions, N being a bit mask of planes. (XO-CHIP)
+pub fn plane_n(vm: &mut Vm) {
+    vm.planes = ((vm.opcode & 0x0F00) >> 8) as u8 & (PLANE_1 | PLANE_2);
+    vm.pc += 2;
+}
+
+// F002 =
+// Load the 16-byte audio pattern buffer from memory
+// starting at address I. (XO-CHIP)
+pub fn audio(vm: &mut Vm) -> Result<(), VmError> {
+    let start = vm.i as usize;
+    vm.check_ram(start, PATTERN_BYTES)?;
+
+    let mut pattern = [0; PATTERN_BYTES];
+    pattern.copy_from_slice(&vm.ram[start..start + PATTERN_BYTES]);
+    vm.sound.pattern = Some(pattern);
+    vm.pc += 2;
+    Ok(())
+}
+
+// FX07 =
+// Set VX to the value of the delay timer
+pub fn ld_vx_dt(vm: &mut Vm) {
+    let x = ((vm.opcode & 0x0F00) >> 8) as usize;
+    vm.v[x] = vm.delay_timer;
+    vm.pc += 2;
+}
+
+// FX0A =
+// A key press is awaited, and then stored in VX.
+pub fn ld_vx_k(vm: &mut Vm) -> CycleOutcome {
+    let x = ((vm.opcode & 0x0F00) >> 8) as usize;
+
+    match vm.key_states.iter().position(|&pressed| pressed) {
+        Some(key) => {
+            vm.v[x] = key as u8;
+            vm.pc += 2;
+            CycleOutcome::Executed
+        }
+        None => CycleOutcome::WaitingForKey,
+    }
+}
+
+// FX15 =
+// Set the delay timer to VX.
+pub fn ld_dt_vx(vm: &mut Vm) {
+    let x = ((vm.opcode & 0x0F00) >> 8) as usize;
+    vm.delay_timer = vm.v[x];
+    vm.pc += 2;
+}
+
+// FX18 =
+// Set the sound timer to VX.
+pub fn ld_st_vx(vm: &mut Vm) {
+    let x = ((vm.opcode & 0x0F00) >> 8) as usize;
+    vm.sound_timer = vm.v[x];
+    vm.pc += 2;
+}
+
+// FX1E =
+// Add VX to I.
+pub fn add_i_vx(vm: &mut Vm) {
+    let x = ((vm.opcode & 0x0F00) >> 8) as usize;
+    let vx = vm.v[x];
+
+    vm.i = vm.i.wrapping_add(vx as u16);
+    vm.pc += 2;
+}
+
+// FX29 =
+// Set I to the location of the sprite for the digit in the low nibble of VX.
+pub fn ld_f_vx(vm: &mut Vm) {
+    let x = ((vm.opcode & 0x0F00) >> 8) as usize;
+    let vx = vm.v[x] & 0x0F;
+
+    vm.i = (FONT_START + vx as usize * FONT_HEIGHT) as u16;
+    vm.pc += 2;
+}
+
+// FX30 =
+// Set I to the location of the big sprite for the digit in VX. (SUPER-CHIP)
+pub fn ld_hf_vx(vm: &mut Vm) {
+    let x = ((vm.opcode & 0x0F00) >> 8) as usize;
+    let vx = vm.v[x] & 0x0F;
+
+    vm.i = (BIG_FONT_START + vx as usize * BIG_FONT_HEIGHT) as u16;
+    vm.pc += 2;
+}
+
+// FX3A =
+// Set the audio pitch register to VX. (XO-CHIP)
+pub fn pitch_vx(vm: &mut Vm) {
+    let x = ((vm.opcode & 0x0F00) >> 8) as usize;
+    vm.sound.pitch = vm.v[x];
+    vm.pc += 2;
+}
+
+// FX33 =
+// Store binary-coded decimal representation of a value
+// contained in VX to addr i, i+1, and i+2.
+pub fn ld_b_vx(vm: &mut Vm) -> Result<(), VmError> {
+    let x = ((vm.opcode & 0x0F00) >> 8) as usize;
+    let vx = vm.v[x];
+
+    vm.check_ram(vm.i as usize, 3)?;
+    vm.ram[vm.i as usize] = vx / 100;
+    vm.ram[(vm.i + 1) as usize] = (vx / 10) % 10;
+    vm.ram[(vm.i + 2) as usize] = (vx % 100) % 10;
+    vm.last_write = Some((vm.i as usize, 3));
+    vm.pc += 2;
+    Ok(())
+}
+
+// FX55 =
+// Store values contained in V0-VX in memory
+// starting at address I.
+// With the `load_store_increments_i` quirk I ends up at I + X + 1.
+pub fn ld_i_vx(vm: &mut Vm) -> Result<(), VmError> {
+    let x = (vm.opcode & 0x0F00) >> 8;
+
+    vm.check_ram(vm.i as usize, x as usize + 1)?;
+    for index in 0..x + 1 {
+        vm.ram[(vm.i + index) as usize] = vm.v[index as usize];
+    }
+    vm.last_write = Some((vm.i as usize, x as usize + 1));
+    if vm.quirks.load_store_increments_i {
+        vm.i = vm.i.wrapping_add(x + 1);
+    }
+    vm.pc += 2;
+    Ok(())
+}
+
+// FX65 =
+// Fills V0-VX with values from memory
+// starting at address I.
+// With the `load_store_increments_i` quirk I ends up at I + X + 1.
+pub fn ld_vx_i(vm: &mut Vm) -> Result<(), VmError> {
+    let x = (vm.opcode & 0x0F00) >> 8;
+
+    vm.check_ram(vm.i as usize, x as usize + 1)?;
+    for index in 0..x + 1 {
+        vm.v[index as usize] = vm.ram[(vm.i + index) as usize];
+    }
+    if vm.quirks.load_store_increments_i {
+        vm.i = vm.i.wrapping_add(x + 1);
+    }
+    vm.pc += 2;
+    Ok(())
+}
+
+// FX75 =
+// Store V0-VX in the RPL user flags. (SUPER-CHIP)
+pub fn ld_r_vx(vm: &mut Vm) {
+    let x = ((vm.opcode & 0x0F00) >> 8) as usize;
+    let count = (x + 1).min(RPL_FLAGS_COUNT);
+
+    vm.rpl[..count].copy_from_slice(&vm.v[..count]);
+    vm.pc += 2;
+}
+
+// FX85 =
+// Fills V0-VX with values from the RPL user flags. (SUPER-CHIP)
+pub fn ld_vx_r(vm: &mut Vm) {
+    let x = ((vm.opcode & 0x0F00) >> 8) as usize;
+    let count = (x + 1).min(RPL_FLAGS_COUNT);
+
+    vm.v[..count].copy_from_slice(&vm.rpl[..count]);
+    vm.pc += 2;
+}
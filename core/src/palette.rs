@@ -0,0 +1,114 @@
+use std::fmt;
+
+/// Colour as red, green and blue components
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+/// Colours of the four plane combinations a pixel can be in
+///
+/// Entry 0 is the background, 1 a pixel lit in the first plane only,
+/// 2 in the second plane only and 3 in both planes. Classic CHIP-8 and
+/// SUPER-CHIP games only ever use the first two entries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Palette {
+    pub colors: [Rgb; 4],
+}
+
+/// Error for palettes that couldn't be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsePaletteError(String);
+
+impl fmt::Display for ParsePaletteError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for ParsePaletteError {}
+
+impl Rgb {
+    /// Parses a colour written as `RRGGBB` hex digits, optionally prefixed with `#`
+    pub fn parse(text: &str) -> Result<Rgb, ParsePaletteError> {
+        let digits = text.trim().trim_start_matches('#');
+        let value = if digits.len() == 6 { u32::from_str_radix(digits, 16).ok() } else { None };
+
+        match value {
+            Some(value) => Ok(Rgb((value >> 16) as u8, (value >> 8) as u8, value as u8)),
+            None => Err(ParsePaletteError(format!("invalid colour '{}', expected RRGGBB", text))),
+        }
+    }
+}
+
+/// Built-in themes, by name
+pub const THEMES: [(&str, Palette); 5] = [
+    ("classic", Palette {
+        colors: [Rgb(0x00, 0x00, 0x00), Rgb(0xFF, 0xFF, 0xFF), Rgb(0xAA, 0xAA, 0xAA), Rgb(0x55, 0x55, 0x55)],
+    }),
+    ("green", Palette {
+        colors: [Rgb(0x0A, 0x14, 0x0A), Rgb(0x33, 0xFF, 0x66), Rgb(0x1A, 0x99, 0x3D), Rgb(0xA8, 0xFF, 0xC0)],
+    }),
+    ("amber", Palette {
+        colors: [Rgb(0x1A, 0x0F, 0x00), Rgb(0xFF, 0xB0, 0x00), Rgb(0x99, 0x5C, 0x00), Rgb(0xFF, 0xDD, 0x88)],
+    }),
+    ("lcd", Palette {
+        colors: [Rgb(0x9B, 0xBC, 0x0F), Rgb(0x0F, 0x38, 0x0F), Rgb(0x30, 0x62, 0x30), Rgb(0x8B, 0xAC, 0x0F)],
+    }),
+    ("octo", Palette {
+        colors: [Rgb(0x99, 0x66, 0x00), Rgb(0xFF, 0xCC, 0x00), Rgb(0xFF, 0x66, 0x00), Rgb(0x66, 0x22, 0x00)],
+    }),
+];
+
+impl Palette {
+    /// Built-in theme named `name`
+    pub fn theme(name: &str) -> Option<Palette> {
+        THEMES.iter().find(|(theme, _)| theme.eq_ignore_ascii_case(name)).map(|&(_, palette)| palette)
+    }
+
+    /// Name of the built-in theme with exactly these colours
+    pub fn theme_name(&self) -> Option<&'static str> {
+        THEMES.iter().find(|(_, palette)| palette == self).map(|&(name, _)| name)
+    }
+
+    /// Built-in theme following this palette, the first theme for
+    /// palettes that aren't a theme
+    pub fn next_theme(&self) -> (&'static str, Palette) {
+        let next = THEMES.iter().position(|(_, palette)| palette == self).map_or(0, |index| index + 1);
+        THEMES[next % THEMES.len()]
+    }
+
+    /// Colour of a screen pixel, i.e. of its bit mask of planes
+    pub fn color(&self, pixel: u8) -> Rgb {
+        self.colors[(pixel & 0b11) as usize]
+    }
+
+    /// Parses the name of a built-in theme, or a comma separated list of
+    /// two or four `RRGGBB` colours. With two colours, the colours of the
+    /// second plane are taken from the default palette.
+    pub fn parse(text: &str) -> Result<Palette, ParsePaletteError> {
+        if let Some(palette) = Palette::theme(text.trim()) {
+            return Ok(palette);
+        }
+        if !text.contains(',') && !text.trim().starts_with('#') && text.trim().len() != 6 {
+            let names = THEMES.iter().map(|&(name, _)| name).collect::<Vec<_>>();
+            return Err(ParsePaletteError(format!("unknown theme '{}', expected one of {} or a list of colours",
+                                                 text, names.join(", "))));
+        }
+        let colors = text.split(',').map(Rgb::parse).collect::<Result<Vec<_>, _>>()?;
+        let mut palette = Palette::default();
+
+        match colors.len() {
+            2 | 4 => palette.colors[..colors.len()].copy_from_slice(&colors),
+            count => {
+                return Err(ParsePaletteError(format!("expected 2 or 4 colours, got {}", count)));
+            }
+        }
+        Ok(palette)
+    }
+}
+
+impl Default for Palette {
+    /// The `classic` theme, white on black
+    fn default() -> Palette {
+        THEMES[0].1
+    }
+}
@@ -0,0 +1,90 @@
+/// Behaviour of the instructions that differ between CHIP-8 implementations
+///
+/// Each flag selects one of the two behaviours of an ambiguous instruction;
+/// the presets bundle the combinations used by the well-known interpreters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quirks {
+    /// `8XY6` / `8XYE` shift VY and store the result in VX,
+    /// instead of shifting VX in place
+    pub shift_uses_vy: bool,
+
+    /// `FX55` / `FX65` leave I pointing right after the last register
+    pub load_store_increments_i: bool,
+
+    /// `BNNN` behaves like `BXNN`, i.e. jumps to XNN plus VX instead of V0
+    pub jump_uses_vx: bool,
+
+    /// `8XY1` / `8XY2` / `8XY3` reset VF to 0
+    pub logic_resets_vf: bool,
+
+    /// `DXYN` clips sprites at the edges of the screen instead of
+    /// wrapping them around to the opposite side
+    pub clip_sprites: bool,
+}
+
+/// Names of the presets accepted by `Quirks::from_name`
+pub const PROFILES: [&str; 4] = ["vip", "schip", "xochip", "modern"];
+
+impl Quirks {
+    /// Original COSMAC VIP interpreter
+    pub fn vip() -> Quirks {
+        Quirks {
+            shift_uses_vy: true,
+            load_store_increments_i: true,
+            jump_uses_vx: false,
+            logic_resets_vf: true,
+            clip_sprites: true,
+        }
+    }
+
+    /// SUPER-CHIP 1.1 on the HP48
+    pub fn schip() -> Quirks {
+        Quirks {
+            shift_uses_vy: false,
+            load_store_increments_i: false,
+            jump_uses_vx: true,
+            logic_resets_vf: false,
+            clip_sprites: true,
+        }
+    }
+
+    /// XO-CHIP as implemented by Octo
+    pub fn xochip() -> Quirks {
+        Quirks {
+            shift_uses_vy: true,
+            load_store_increments_i: true,
+            jump_uses_vx: false,
+            logic_resets_vf: false,
+            clip_sprites: false,
+        }
+    }
+
+    /// Behaviour most modern interpreters (and the majority of ROMs
+    /// found on the Internet) settled on
+    pub fn modern() -> Quirks {
+        Quirks {
+            shift_uses_vy: false,
+            load_store_increments_i: false,
+            jump_uses_vx: false,
+            logic_resets_vf: false,
+            clip_sprites: false,
+        }
+    }
+
+    /// Looks up a preset by its name, see `PROFILES`
+    pub fn from_name(name: &str) -> Option<Quirks> {
+        match name {
+            "vip" => Some(Quirks::vip()),
+            "schip" => Some(Quirks::schip()),
+            "xochip" => Some(Quirks::xochip()),
+            "modern" => Some(Quirks::modern()),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Quirks {
+    fn default() -> Quirks {
+        Quirks::modern()
+    }
+}
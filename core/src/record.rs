@@ -0,0 +1,92 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::clock::FRAME_RATE;
+use crate::frame::{to_io_error, Frame};
+use crate::palette::Palette;
+use crate::vm::Vm;
+
+/// Most distinct frames a recording holds, a minute of a screen changing
+/// every frame
+pub const MAX_RECORDED_FRAMES: usize = 3600;
+
+/// Records the screen once per frame into an animated PNG (APNG)
+///
+/// Consecutive identical frames are stored once, shown for as many frames
+/// as they lasted. Every pixel of the widest frame becomes a `scale` x
+/// `scale` square; when the game switches to the high resolution mode,
+/// the low resolution frames are drawn at twice the scale so both modes
+/// fill the same image.
+#[derive(Debug, Clone)]
+pub struct Recorder {
+    palette: Palette,
+    scale: usize,
+    /// Distinct frames and how many 60 Hz frames each lasted
+    frames: Vec<(Frame, u16)>,
+}
+
+impl Recorder {
+    pub fn new(palette: Palette, scale: usize) -> Recorder {
+        Recorder {
+            palette,
+            scale: scale.max(1),
+            frames: Vec::new(),
+        }
+    }
+
+    /// Number of 60 Hz frames recorded so far
+    pub fn len(&self) -> u64 {
+        self.frames.iter().map(|&(_, count)| count as u64).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Whether the recording holds `MAX_RECORDED_FRAMES` distinct frames
+    pub fn is_full(&self) -> bool {
+        self.frames.len() >= MAX_RECORDED_FRAMES
+    }
+
+    /// Adds the current screen as the next frame, returning false when
+    /// the recording is full and the frame was dropped
+    pub fn capture(&mut self, vm: &Vm) -> bool {
+        let frame = Frame::capture(vm);
+        let full = self.is_full();
+        match self.frames.last_mut() {
+            Some((last, count)) if *last == frame && *count < u16::MAX => *count += 1,
+            _ if full => return false,
+            _ => self.frames.push((frame, 1)),
+        }
+        true
+    }
+
+    /// Encodes the recording, looping forever
+    pub fn write<W: Write>(&self, writer: W) -> io::Result<()> {
+        if self.frames.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "no frames recorded"));
+        }
+
+        // every frame is scaled to the size of the widest one
+        let (widest, _) = self.frames.iter().max_by_key(|(frame, _)| frame.width).unwrap();
+        let (width, height) = (widest.width * self.scale, widest.height * self.scale);
+
+        let mut encoder = png::Encoder::new(writer, width as u32, height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_animated(self.frames.len() as u32, 0).map_err(to_io_error)?;
+        let mut writer = encoder.write_header().map_err(to_io_error)?;
+        for (frame, count) in &self.frames {
+            let scale = self.scale * widest.width / frame.width;
+            writer.set_frame_delay(*count, FRAME_RATE as u16).map_err(to_io_error)?;
+            writer.write_image_data(&frame.to_rgb(&self.palette, scale)).map_err(to_io_error)?;
+        }
+        writer.finish().map_err(to_io_error)
+    }
+
+    /// Writes the recording to the file at `path`
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.write(BufWriter::new(File::create(path)?))
+    }
+}
@@ -0,0 +1,205 @@
+//! Rewind buffer
+//!
+//! Keeps the most recent save states of the machine. Only the newest
+//! snapshot is stored in full; every older one is stored as the XOR of
+//! itself and its successor, run-length encoded. Consecutive frames
+//! differ in a few bytes of RAM and screen, so a delta usually takes a
+//! few dozen bytes instead of the full `STATE_SIZE`.
+
+use std::collections::VecDeque;
+
+use crate::vm::Vm;
+
+/// Default number of snapshots, ten seconds at one snapshot per frame
+pub const DEFAULT_REWIND_DEPTH: usize = 600;
+
+/// Ring buffer of snapshots for stepping the machine backwards
+pub struct Rewind {
+    depth: usize,
+    /// Newest snapshot, `None` when the buffer is empty
+    latest: Option<Vec<u8>>,
+    /// Encoded deltas, the last one turns `latest` into the snapshot before it
+    deltas: VecDeque<Vec<u8>>,
+}
+
+impl Rewind {
+    /// Creates a buffer keeping up to `depth` snapshots, 0 disables it
+    pub fn new(depth: usize) -> Rewind {
+        Rewind {
+            depth,
+            latest: None,
+            deltas: VecDeque::new(),
+        }
+    }
+
+    /// Number of snapshots held
+    pub fn len(&self) -> usize {
+        self.latest.as_ref().map_or(0, |_| self.deltas.len() + 1)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.latest.is_none()
+    }
+
+    /// Drops all snapshots
+    pub fn clear(&mut self) {
+        self.latest = None;
+        self.deltas.clear();
+    }
+
+    /// Bytes used by the snapshots
+    pub fn memory_usage(&self) -> usize {
+        self.latest.as_ref().map_or(0, Vec::len) + self.deltas.iter().map(Vec::len).sum::<usize>()
+    }
+
+    /// Records a snapshot of the machine, dropping the oldest one when full
+    pub fn push(&mut self, vm: &Vm) {
+        if self.depth == 0 {
+            return;
+        }
+        let state = vm.save_state();
+        if let Some(latest) = self.latest.take() {
+            self.deltas.push_back(encode_delta(&latest, &state));
+            if self.deltas.len() >= self.depth {
+                self.deltas.pop_front();
+            }
+        }
+        self.latest = Some(state);
+    }
+
+    /// Restores the newest snapshot and removes it from the buffer
+    ///
+    /// Returns false when the buffer is empty, the machine then stays in
+    /// the oldest state that was kept.
+    pub fn pop(&mut self, vm: &mut Vm) -> bool {
+        let mut latest = match self.latest.take() {
+            Some(latest) => latest,
+            None => return false,
+        };
+        if vm.load_state(&latest).is_err() {
+            self.clear();
+            return false;
+        }
+        if let Some(delta) = self.deltas.pop_back() {
+            apply_delta(&mut latest, &delta);
+            self.latest = Some(latest);
+        }
+        true
+    }
+}
+
+/// Encodes `from XOR to` as the length of `from` as a `u32`, followed by
+/// runs of `[skip: u16][len: u16][len bytes]` skipping the unchanged
+/// bytes; the shorter state is taken as padded with zeros
+fn encode_delta(from: &[u8], to: &[u8]) -> Vec<u8> {
+    let mut delta = (from.len() as u32).to_be_bytes().to_vec();
+    let size = from.len().max(to.len());
+    let xor = |index: usize| from.get(index).unwrap_or(&0) ^ to.get(index).unwrap_or(&0);
+    let mut index = 0;
+
+    while index < size {
+        let start = index;
+        while index < size && index - start < 0xFFFF && xor(index) == 0 {
+            index += 1;
+        }
+        let skip = index - start;
+
+        let literal = index;
+        while index < size && index - literal < 0xFFFF && xor(index) != 0 {
+            index += 1;
+        }
+        if index == literal && index == size {
+            break;
+        }
+        delta.extend_from_slice(&(skip as u16).to_be_bytes());
+        delta.extend_from_slice(&((index - literal) as u16).to_be_bytes());
+        delta.extend((literal..index).map(xor));
+    }
+    delta
+}
+
+/// XORs the runs of `delta` into `state`, turning it into the `from`
+/// state of `encode_delta`
+fn apply_delta(state: &mut Vec<u8>, delta: &[u8]) {
+    let size = u32::from_be_bytes([delta[0], delta[1], delta[2], delta[3]]) as usize;
+    let mut index = 0;
+    let mut runs = &delta[4..];
+
+    state.resize(state.len().max(size), 0);
+    while runs.len() >= 4 {
+        let skip = u16::from_be_bytes([runs[0], runs[1]]) as usize;
+        let len = u16::from_be_bytes([runs[2], runs[3]]) as usize;
+        index += skip;
+        for (byte, change) in state[index..index + len].iter_mut().zip(&runs[4..4 + len]) {
+            *byte ^= change;
+        }
+        index += len;
+        runs = &runs[4 + len..];
+    }
+    state.truncate(size);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Round trips `from` through a delta against `to`
+    fn round_trip(from: &[u8], to: &[u8]) {
+        let mut state = to.to_vec();
+        apply_delta(&mut state, &encode_delta(from, to));
+        assert_eq!(state, from);
+    }
+
+    #[test]
+    fn restores_every_pushed_state() {
+        let mut vm = Vm::init();
+        let mut rewind = Rewind::new(DEFAULT_REWIND_DEPTH);
+        let mut states = Vec::new();
+
+        for step in 0..8u8 {
+            match step {
+                // every byte of RAM changes, a run longer than 0xFFFF bytes
+                0 => vm.ram.iter_mut().enumerate().for_each(|(addr, byte)| *byte = addr as u8 | 1),
+                1 => vm.ram.iter_mut().for_each(|byte| *byte = 0),
+                2 => vm.screen.iter_mut().step_by(3).for_each(|pixel| *pixel = 1),
+                _ => {
+                    vm.v[step as usize] = step;
+                    vm.ram[0x200 + step as usize * 0x1111] = step;
+                    vm.pc += 2;
+                }
+            }
+            rewind.push(&vm);
+            states.push(vm.save_state());
+        }
+
+        assert_eq!(rewind.len(), states.len());
+        while let Some(expected) = states.pop() {
+            assert!(rewind.pop(&mut vm));
+            assert_eq!(vm.save_state(), expected);
+        }
+        assert!(!rewind.pop(&mut vm));
+    }
+
+    #[test]
+    fn round_trips_long_runs() {
+        let zeros = vec![0; 0x30000];
+        let mut mixed = zeros.clone();
+        mixed[0x20000..].iter_mut().enumerate().for_each(|(index, byte)| *byte = index as u8 | 0x80);
+        mixed[5] = 1;
+
+        round_trip(&zeros, &mixed);
+        round_trip(&mixed, &zeros);
+        round_trip(&mixed, &mixed);
+    }
+
+    #[test]
+    fn round_trips_states_of_different_sizes() {
+        let short = vec![1, 2, 3, 4, 5];
+        let long = (0..0x12000).map(|index| (index % 7) as u8).collect::<Vec<_>>();
+
+        round_trip(&short, &long);
+        round_trip(&long, &short);
+        round_trip(&[], &short);
+        round_trip(&short, &[]);
+    }
+}
@@ -0,0 +1,283 @@
+//! Save states
+//!
+//! A state is a fixed-size binary snapshot of the machine, made of a
+//! header (`STATE_MAGIC`, `STATE_VERSION` and the SHA-1 of the ROM)
+//! followed by the machine state in big-endian order. Quirks aren't part
+//! of a state, they belong to the settings the game is run with.
+
+use std::error::Error;
+use std::fmt;
+
+use crate::audio::{Sound, PATTERN_BYTES};
+use crate::vm::*;
+
+/// First bytes of every save state
+pub const STATE_MAGIC: [u8; 4] = *b"C8ST";
+/// Version of the save state format, bumped on every layout change
+pub const STATE_VERSION: u16 = 2;
+
+const HEADER_SIZE: usize = 4 + 2 + 20;
+
+/// Size of a save state in bytes
+pub const STATE_SIZE: usize = HEADER_SIZE
+    + 2 + RAM_SIZE + DATA_REGISTERS_COUNT + 2 + 4
+    + SCREEN_PIXELS + 1 + 1 + KEYS_COUNT
+    + 1 + 1 + 1 + PATTERN_BYTES + 1
+    + STACK_SIZE * 2 + 1
+    + RPL_FLAGS_COUNT;
+
+/// Errors raised while restoring a save state
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StateError {
+    /// The data isn't a save state
+    NotAState,
+
+    /// The state was written by an incompatible version
+    UnsupportedVersion(u16),
+
+    /// The state was saved while running a different ROM
+    RomMismatch,
+
+    /// The state holds values the machine can't be in
+    Corrupt(&'static str),
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StateError::NotAState => write!(f, "not a save state"),
+            StateError::UnsupportedVersion(version) => {
+                write!(f, "unsupported save state version {}, expected {}", version, STATE_VERSION)
+            }
+            StateError::RomMismatch => write!(f, "save state belongs to a different ROM"),
+            StateError::Corrupt(what) => write!(f, "corrupt save state: {}", what),
+        }
+    }
+}
+
+impl Error for StateError {}
+
+/// Reads consecutive fields of a state
+struct Fields<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Fields<'a> {
+    fn take(&mut self, len: usize) -> &'a [u8] {
+        let (field, rest) = self.data.split_at(len);
+        self.data = rest;
+        field
+    }
+
+    fn u8(&mut self) -> u8 {
+        self.take(1)[0]
+    }
+
+    fn u16(&mut self) -> u16 {
+        let bytes = self.take(2);
+        u16::from_be_bytes([bytes[0], bytes[1]])
+    }
+
+    fn u32(&mut self) -> u32 {
+        let bytes = self.take(4);
+        u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+    }
+}
+
+impl Vm {
+    /// Snapshots the machine, see the module documentation for the format
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut state = Vec::with_capacity(STATE_SIZE);
+
+        state.extend_from_slice(&STATE_MAGIC);
+        state.extend_from_slice(&STATE_VERSION.to_be_bytes());
+        state.extend_from_slice(&self.rom_hash);
+
+        state.extend_from_slice(&self.opcode.to_be_bytes());
+        state.extend_from_slice(&self.ram);
+        state.extend_from_slice(&self.v);
+        state.extend_from_slice(&self.i.to_be_bytes());
+        state.extend_from_slice(&(self.pc as u32).to_be_bytes());
+
+        state.extend_from_slice(&self.screen);
+        state.push(self.hires as u8);
+        state.push(self.planes);
+        state.extend(self.key_states.iter().map(|&pressed| pressed as u8));
+
+        state.push(self.delay_timer);
+        state.push(self.sound_timer);
+        state.push(self.sound.pattern.is_some() as u8);
+        state.extend_from_slice(&self.sound.pattern.unwrap_or([0; PATTERN_BYTES]));
+        state.push(self.sound.pitch);
+
+        for addr in &self.stack {
+            state.extend_from_slice(&addr.to_be_bytes());
+        }
+        state.push(self.sp as u8);
+
+        state.extend_from_slice(&self.rpl);
+
+        debug_assert_eq!(state.len(), STATE_SIZE);
+        state
+    }
+
+    /// Restores a snapshot taken by `save_state` while running the same ROM
+    ///
+    /// The machine is left untouched when the state is rejected.
+    pub fn load_state(&mut self, state: &[u8]) -> Result<(), StateError> {
+        if state.len() < HEADER_SIZE || state[..4] != STATE_MAGIC {
+            return Err(StateError::NotAState);
+        }
+        let mut fields = Fields { data: &state[4..] };
+        let version = fields.u16();
+        if version != STATE_VERSION {
+            return Err(StateError::UnsupportedVersion(version));
+        }
+        if fields.take(20) != self.rom_hash {
+            return Err(StateError::RomMismatch);
+        }
+        if state.len() != STATE_SIZE {
+            return Err(StateError::Corrupt("wrong size"));
+        }
+
+        let opcode = fields.u16();
+        let ram = fields.take(RAM_SIZE);
+        let v = fields.take(DATA_REGISTERS_COUNT);
+        let i = fields.u16();
+        let pc = fields.u32() as usize;
+        if pc >= RAM_SIZE {
+            return Err(StateError::Corrupt("PC outside of RAM"));
+        }
+
+        let screen = fields.take(SCREEN_PIXELS);
+        let hires = fields.u8() != 0;
+        let planes = fields.u8();
+        if planes > PLANE_1 | PLANE_2 {
+            return Err(StateError::Corrupt("invalid planes"));
+        }
+        let key_states = fields.take(KEYS_COUNT);
+
+        let delay_timer = fields.u8();
+        let sound_timer = fields.u8();
+        let has_pattern = fields.u8() != 0;
+        let pattern = fields.take(PATTERN_BYTES);
+        let pitch = fields.u8();
+
+        let stack = fields.take(STACK_SIZE * 2);
+        let sp = fields.u8() as usize;
+        if sp > STACK_SIZE {
+            return Err(StateError::Corrupt("stack pointer out of range"));
+        }
+
+        let rpl = fields.take(RPL_FLAGS_COUNT);
+
+        self.opcode = opcode;
+        self.ram.copy_from_slice(ram);
+        self.v.copy_from_slice(v);
+        self.i = i;
+        self.pc = pc;
+        self.screen.copy_from_slice(screen);
+        self.hires = hires;
+        self.planes = planes;
+        for (pressed, &byte) in self.key_states.iter_mut().zip(key_states) {
+            *pressed = byte != 0;
+        }
+        self.delay_timer = delay_timer;
+        self.sound_timer = sound_timer;
+        self.sound = Sound {
+            pattern: if has_pattern {
+                let mut buffer = [0; PATTERN_BYTES];
+                buffer.copy_from_slice(pattern);
+                Some(buffer)
+            } else {
+                None
+            },
+            pitch,
+        };
+        for (addr, bytes) in self.stack.iter_mut().zip(stack.chunks(2)) {
+            *addr = u16::from_be_bytes([bytes[0], bytes[1]]);
+        }
+        self.sp = sp;
+        self.rpl.copy_from_slice(rpl);
+
+        self.last_write = None;
+        self.draw_flag = true;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROM: [u8; 4] = [0x60, 0x2A, 0x12, 0x02];
+
+    /// A machine running `ROM` with every part of its state changed
+    fn running_machine() -> Vm {
+        let mut vm = Vm::init();
+        vm.load_rom(&ROM).unwrap();
+        vm.opcode = 0x602A;
+        vm.ram[0xFFF] = 0x5A;
+        vm.v[0xF] = 1;
+        vm.i = 0x345;
+        vm.pc = 0x202;
+        vm.screen[10] = PLANE_1 | PLANE_2;
+        vm.hires = true;
+        vm.planes = PLANE_2;
+        vm.key_states[7] = true;
+        vm.delay_timer = 20;
+        vm.sound_timer = 3;
+        vm.sound = Sound { pattern: Some([0xF0; PATTERN_BYTES]), pitch: 80 };
+        vm.stack[0] = 0x204;
+        vm.sp = 1;
+        vm.rpl[2] = 9;
+        vm
+    }
+
+    #[test]
+    fn round_trip_restores_the_machine() {
+        let saved = running_machine();
+        let mut vm = Vm::init();
+        vm.load_rom(&ROM).unwrap();
+        vm.load_state(&saved.save_state()).unwrap();
+
+        assert_eq!(vm.opcode, saved.opcode);
+        assert_eq!(vm.ram[..], saved.ram[..]);
+        assert_eq!(vm.v, saved.v);
+        assert_eq!(vm.i, saved.i);
+        assert_eq!(vm.pc, saved.pc);
+        assert_eq!(vm.screen[..], saved.screen[..]);
+        assert_eq!(vm.hires, saved.hires);
+        assert_eq!(vm.planes, saved.planes);
+        assert_eq!(vm.key_states, saved.key_states);
+        assert_eq!(vm.delay_timer, saved.delay_timer);
+        assert_eq!(vm.sound_timer, saved.sound_timer);
+        assert_eq!(vm.sound.pattern, saved.sound.pattern);
+        assert_eq!(vm.sound.pitch, saved.sound.pitch);
+        assert_eq!(vm.stack, saved.stack);
+        assert_eq!(vm.sp, saved.sp);
+        assert_eq!(vm.rpl, saved.rpl);
+        assert_eq!(vm.save_state(), saved.save_state());
+    }
+
+    #[test]
+    fn rejects_a_state_of_another_rom() {
+        let state = running_machine().save_state();
+        let mut vm = Vm::init();
+        vm.load_rom(&[0x00, 0xE0]).unwrap();
+
+        assert_eq!(vm.load_state(&state), Err(StateError::RomMismatch));
+        assert_eq!(vm.pc, 0x200);
+    }
+
+    #[test]
+    fn rejects_another_version() {
+        let mut state = running_machine().save_state();
+        state[4..6].copy_from_slice(&(STATE_VERSION + 1).to_be_bytes());
+        let mut vm = Vm::init();
+        vm.load_rom(&ROM).unwrap();
+
+        assert_eq!(vm.load_state(&state), Err(StateError::UnsupportedVersion(STATE_VERSION + 1)));
+        assert_eq!(vm.pc, 0x200);
+    }
+}
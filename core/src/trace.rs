@@ -0,0 +1,336 @@
+//! Instruction trace
+//!
+//! One line per executed instruction, holding the machine state right
+//! before the instruction runs:
+//!
+//! ```text
+//! 12 0206 D015 1E 0A 00 00 00 00 00 00 00 00 00 00 00 00 00 00 I=0210 SP=0 DRW V0, V1, 5
+//! ```
+//!
+//! that is the cycle number, PC, opcode, V0 to VF, I, SP and the
+//! disassembly. Traces can also hold a hash of the screen, written as
+//! `S=XXXXXXXX` before the disassembly. Values are upper case hex
+//! without prefix and the same run always gives the same trace, so the
+//! traces of two runs can be compared line by line with `diff`.
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::ops::RangeInclusive;
+use std::path::Path;
+
+use crate::disasm;
+use crate::vm::{Vm, DATA_REGISTERS_COUNT};
+
+/// Opcode classes of a filter keeping all of them
+const ALL_CLASSES: u16 = 0xFFFF;
+
+/// Instructions kept in a trace
+///
+/// Classes are the high nibbles of the opcodes: class `D` holds the `DXYN`
+/// draws, class `8` the `8XYN` arithmetic and so on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceFilter {
+    /// Addresses of the traced instructions, any address when `None`
+    pub pcs: Option<RangeInclusive<usize>>,
+    /// Bit mask of the traced classes, bit N for class N
+    pub classes: u16,
+}
+
+impl TraceFilter {
+    /// Parses an address range written as `200-2FF` in hex, or a single address
+    pub fn parse_pcs(text: &str) -> Result<RangeInclusive<usize>, String> {
+        let address = |text: &str| {
+            usize::from_str_radix(text.trim().trim_start_matches("0x"), 16)
+                .map_err(|_| format!("invalid address '{}'", text))
+        };
+        let (start, end) = match text.find('-') {
+            Some(dash) => (address(&text[..dash])?, address(&text[dash + 1..])?),
+            None => (address(text)?, address(text)?),
+        };
+        if start > end {
+            return Err(format!("empty address range '{}'", text));
+        }
+        Ok(start..=end)
+    }
+
+    /// Parses a comma separated list of classes, the hex digits `0` to `F`
+    pub fn parse_classes(text: &str) -> Result<u16, String> {
+        text.split(',').try_fold(0, |classes, class| {
+            match u8::from_str_radix(class.trim(), 16) {
+                Ok(class) if class < 0x10 => Ok(classes | 1 << class),
+                _ => Err(format!("invalid opcode class '{}', expected 0 to F", class)),
+            }
+        })
+    }
+
+    pub fn matches(&self, pc: usize, opcode: u16) -> bool {
+        self.classes & 1 << (opcode >> 12) != 0
+            && self.pcs.as_ref().is_none_or(|pcs| pcs.contains(&pc))
+    }
+}
+
+impl Default for TraceFilter {
+    fn default() -> TraceFilter {
+        TraceFilter {
+            pcs: None,
+            classes: ALL_CLASSES,
+        }
+    }
+}
+
+/// One line of a trace
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceLine {
+    pub cycle: u64,
+    pub pc: usize,
+    pub opcode: u16,
+    pub v: [u8; DATA_REGISTERS_COUNT],
+    pub i: u16,
+    pub sp: usize,
+    /// `screen_hash` of the screen, only in traces asked to include it
+    pub screen: Option<u32>,
+    pub disassembly: String,
+}
+
+impl TraceLine {
+    /// Takes the state of the machine before it executes the instruction at PC
+    pub fn capture(vm: &Vm, cycle: u64, with_screen: bool) -> TraceLine {
+        TraceLine {
+            cycle,
+            pc: vm.pc,
+            opcode: opcode_at(vm),
+            v: vm.v,
+            i: vm.i,
+            sp: vm.sp,
+            screen: if with_screen { Some(screen_hash(vm)) } else { None },
+            disassembly: disasm::decode_at(&vm.ram, vm.pc)
+                .map_or_else(|| "??".to_string(), |(instruction, _)| instruction.to_string()),
+        }
+    }
+
+    /// Parses a line written by `Display`
+    pub fn parse(text: &str) -> Result<TraceLine, String> {
+        let mut fields = text.split_whitespace();
+        let mut field = |name: &str| fields.next().ok_or_else(|| format!("missing {}", name));
+        let hex = |name: &str, value: &str| {
+            u32::from_str_radix(value, 16).map_err(|_| format!("invalid {} '{}'", name, value))
+        };
+        let prefixed = |prefix: &str, value: &str| {
+            value.strip_prefix(prefix).ok_or_else(|| format!("expected {}, got '{}'", prefix, value))
+                .and_then(|digits| hex(prefix.trim_end_matches('='), digits))
+        };
+
+        let cycle = field("cycle")?;
+        let cycle = cycle.parse::<u64>().map_err(|_| format!("invalid cycle '{}'", cycle))?;
+        let pc = hex("PC", field("PC")?)? as usize;
+        let opcode = hex("opcode", field("opcode")?)? as u16;
+        let mut v = [0; DATA_REGISTERS_COUNT];
+        for (index, register) in v.iter_mut().enumerate() {
+            *register = hex("register", field(&format!("V{:X}", index))?)? as u8;
+        }
+        let i = prefixed("I=", field("I")?)? as u16;
+        let sp = prefixed("SP=", field("SP")?)? as usize;
+        let mut rest = field("disassembly").unwrap_or("");
+        let screen = match rest.strip_prefix("S=") {
+            Some(_) => {
+                let screen = prefixed("S=", rest)?;
+                rest = field("disassembly").unwrap_or("");
+                Some(screen)
+            }
+            None => None,
+        };
+        let disassembly = std::iter::once(rest).chain(fields).collect::<Vec<_>>().join(" ");
+
+        Ok(TraceLine { cycle, pc, opcode, v, i, sp, screen, disassembly })
+    }
+}
+
+impl TraceLine {
+    /// Fields of the machine state that differ from `actual`, as name,
+    /// expected and actual value; the screens are only compared when both
+    /// lines have a hash
+    pub fn differences(&self, actual: &TraceLine) -> Vec<(String, String, String)> {
+        let mut differences = Vec::new();
+        let mut compare = |name: String, expected: String, got: String| {
+            if expected != got {
+                differences.push((name, expected, got));
+            }
+        };
+
+        compare("PC".to_string(), format!("{:04X}", self.pc), format!("{:04X}", actual.pc));
+        compare("opcode".to_string(), format!("{:04X}", self.opcode), format!("{:04X}", actual.opcode));
+        for (index, (expected, got)) in self.v.iter().zip(actual.v.iter()).enumerate() {
+            compare(format!("V{:X}", index), format!("{:02X}", expected), format!("{:02X}", got));
+        }
+        compare("I".to_string(), format!("{:04X}", self.i), format!("{:04X}", actual.i));
+        compare("SP".to_string(), format!("{:X}", self.sp), format!("{:X}", actual.sp));
+        if let (Some(expected), Some(got)) = (self.screen, actual.screen) {
+            compare("screen".to_string(), format!("{:08X}", expected), format!("{:08X}", got));
+        }
+        differences
+    }
+}
+
+impl fmt::Display for TraceLine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {:04X} {:04X}", self.cycle, self.pc, self.opcode)?;
+        for v in self.v.iter() {
+            write!(f, " {:02X}", v)?;
+        }
+        write!(f, " I={:04X} SP={:X}", self.i, self.sp)?;
+        if let Some(screen) = self.screen {
+            write!(f, " S={:08X}", screen)?;
+        }
+        write!(f, " {}", self.disassembly)
+    }
+}
+
+/// FNV-1a hash of the pixels of the screen in its current display mode
+pub fn screen_hash(vm: &Vm) -> u32 {
+    let pixels = &vm.screen[..vm.screen_width() * vm.screen_height()];
+    pixels.iter().fold(0x811C_9DC5, |hash, &pixel| (hash ^ pixel as u32).wrapping_mul(0x0100_0193))
+}
+
+/// Opcode at PC, 0 past the end of memory
+fn opcode_at(vm: &Vm) -> u16 {
+    match vm.ram.get(vm.pc..vm.pc + 2) {
+        Some(bytes) => (bytes[0] as u16) << 8 | bytes[1] as u16,
+        None => 0,
+    }
+}
+
+/// Writes one line per executed instruction, see the module documentation
+pub struct Tracer {
+    out: Box<dyn Write>,
+    filter: TraceFilter,
+    with_screen: bool,
+    cycle: u64,
+    /// First write error, reported by `finish`
+    error: Option<io::Error>,
+}
+
+impl Tracer {
+    pub fn new<W: Write + 'static>(out: W) -> Tracer {
+        Tracer {
+            out: Box::new(out),
+            filter: TraceFilter::default(),
+            with_screen: false,
+            cycle: 0,
+            error: None,
+        }
+    }
+
+    /// Creates a tracer writing to the file at `path`
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Tracer> {
+        Ok(Tracer::new(BufWriter::new(File::create(path)?)))
+    }
+
+    /// Only logs the instructions kept by `filter`; the others are still
+    /// counted, so cycle numbers stay comparable between traces
+    pub fn set_filter(&mut self, filter: TraceFilter) {
+        self.filter = filter;
+    }
+
+    /// Adds the `screen_hash` to every line, before the disassembly
+    pub fn set_screen_hash(&mut self, with_screen: bool) {
+        self.with_screen = with_screen;
+    }
+
+    /// Logs the instruction at PC, called before it executes
+    pub fn record(&mut self, vm: &Vm) {
+        if self.error.is_none() && self.filter.matches(vm.pc, opcode_at(vm)) {
+            let written = writeln!(self.out, "{}", TraceLine::capture(vm, self.cycle, self.with_screen));
+            self.error = written.err();
+        }
+        self.cycle += 1;
+    }
+
+    /// Flushes the trace, returning the first error met while writing it
+    pub fn finish(mut self) -> io::Result<()> {
+        match self.error.take() {
+            Some(error) => Err(error),
+            None => self.out.flush(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(screen: Option<u32>) -> TraceLine {
+        let mut v = [0; DATA_REGISTERS_COUNT];
+        v[0x0] = 0x1E;
+        v[0xF] = 0x01;
+        TraceLine {
+            cycle: 12,
+            pc: 0x206,
+            opcode: 0xD015,
+            v,
+            i: 0x210,
+            sp: 3,
+            screen,
+            disassembly: "DRW V0, V1, 5".to_string(),
+        }
+    }
+
+    #[test]
+    fn parses_formatted_lines_back() {
+        let plain = line(None);
+        assert_eq!(plain.to_string(),
+                   "12 0206 D015 1E 00 00 00 00 00 00 00 00 00 00 00 00 00 00 01 I=0210 SP=3 DRW V0, V1, 5");
+        assert_eq!(TraceLine::parse(&plain.to_string()), Ok(plain));
+
+        let with_screen = line(Some(0x0BAD_F00D));
+        assert!(with_screen.to_string().ends_with(" SP=3 S=0BADF00D DRW V0, V1, 5"));
+        assert_eq!(TraceLine::parse(&with_screen.to_string()), Ok(with_screen));
+    }
+
+    #[test]
+    fn rejects_malformed_lines() {
+        assert_eq!(TraceLine::parse("12 0206"), Err("missing opcode".to_string()));
+        assert_eq!(TraceLine::parse("x 0206 D015"), Err("invalid cycle 'x'".to_string()));
+        let text = line(None).to_string().replace("I=0210", "J=0210");
+        assert_eq!(TraceLine::parse(&text), Err("expected I=, got 'J=0210'".to_string()));
+    }
+
+    #[test]
+    fn reports_each_differing_field() {
+        let expected = line(Some(1));
+        let mut actual = line(Some(2));
+        actual.pc = 0x208;
+        actual.opcode = 0x00E0;
+        actual.v[0x3] = 0x7F;
+        actual.i = 0x300;
+        actual.sp = 0;
+        actual.disassembly = "CLS".to_string();
+
+        let names: Vec<String> = expected.differences(&actual).into_iter().map(|(name, _, _)| name).collect();
+        assert_eq!(names, ["PC", "opcode", "V3", "I", "SP", "screen"]);
+        assert_eq!(expected.differences(&actual)[2], ("V3".to_string(), "00".to_string(), "7F".to_string()));
+    }
+
+    #[test]
+    fn compares_screens_only_when_both_lines_have_one() {
+        assert!(line(None).differences(&line(Some(2))).is_empty());
+        assert!(line(Some(1)).differences(&line(None)).is_empty());
+        assert!(line(Some(1)).differences(&line(Some(1))).is_empty());
+    }
+
+    #[test]
+    fn filters_by_address_and_class() {
+        assert_eq!(TraceFilter::parse_pcs("200-2FF"), Ok(0x200..=0x2FF));
+        assert_eq!(TraceFilter::parse_pcs("0x2A0"), Ok(0x2A0..=0x2A0));
+        assert!(TraceFilter::parse_pcs("300-200").is_err());
+        assert!(TraceFilter::parse_pcs("20G").is_err());
+        assert_eq!(TraceFilter::parse_classes("D, f,0"), Ok(1 << 0xD | 1 << 0xF | 1));
+        assert!(TraceFilter::parse_classes("10").is_err());
+
+        let filter = TraceFilter { pcs: Some(0x200..=0x20F), classes: 1 << 0xD };
+        assert!(filter.matches(0x206, 0xD015));
+        assert!(!filter.matches(0x206, 0x00E0));
+        assert!(!filter.matches(0x210, 0xD015));
+        assert!(TraceFilter::default().matches(0xFFE, 0x00E0));
+    }
+}
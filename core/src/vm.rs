@@ -0,0 +1,430 @@
+use rand::rngs::StdRng;
+use rand::{FromEntropy, SeedableRng};
+use sha1_smol::Sha1;
+
+use crate::audio::Sound;
+use crate::error::{CycleOutcome, VmError};
+use crate::font::{BIG_FONT, BIG_FONT_BYTES, BIG_FONT_START, FONT, FONT_BYTES, FONT_START};
+use crate::frontend::Frontend;
+use crate::loader::{self, LoadError};
+use crate::opcodes::*;
+use crate::quirks::Quirks;
+
+/// Size of the RAM in bytes, the full XO-CHIP address space
+pub const RAM_SIZE: usize = 0x10000;
+/// Depth of the stack
+pub const STACK_SIZE: usize = 16;
+/// Number of data registers, i.e. `V0` .. `VF`
+pub const DATA_REGISTERS_COUNT: usize = 16;
+/// Memory address for program (ROM) start
+pub const PROGRAM_START: usize = 0x200;
+
+/// Width of the screen in pixels
+pub const SCREEN_WIDTH: usize = 64;
+/// Height of the screen in pixels
+pub const SCREEN_HEIGHT: usize = 32;
+/// Width of the screen in pixels in the SUPER-CHIP high resolution mode
+pub const HIRES_SCREEN_WIDTH: usize = 128;
+/// Height of the screen in pixels in the SUPER-CHIP high resolution mode
+pub const HIRES_SCREEN_HEIGHT: usize = 64;
+/// Total number of pixels of the screen buffer, enough for either mode
+pub const SCREEN_PIXELS: usize = HIRES_SCREEN_WIDTH * HIRES_SCREEN_HEIGHT;
+
+/// Bit of a screen pixel belonging to the first plane
+pub const PLANE_1: u8 = 0b01;
+/// Bit of a screen pixel belonging to the second (XO-CHIP) plane
+pub const PLANE_2: u8 = 0b10;
+
+/// Number of RPL user flags saved by `FX75`
+pub const RPL_FLAGS_COUNT: usize = 16;
+
+/// Default number of instructions executed per 60 Hz frame
+pub const CYCLES_PER_FRAME: usize = 10;
+
+/// Number of keys on the keypad
+pub const KEYS_COUNT: usize = 16;
+
+/// Virtual machine
+///
+/// The virtual machine manages state like its registers,
+/// RAM, stack, screen pixels, pressed keys as well as
+/// timers and some internal state.
+pub struct Vm {
+    pub opcode: u16,
+    pub ram: [u8; RAM_SIZE],
+
+    /// CPU registers (V0-VF)
+    pub v: [u8; DATA_REGISTERS_COUNT],
+
+    /// Index register and program counter
+    pub i: u16,
+    pub pc: usize,
+
+    /// Screen: 64 x 32 pixels, or 128 x 64 pixels in high resolution mode.
+    /// Rows are `screen_width()` pixels apart. Each pixel is a bit mask
+    /// of the planes (`PLANE_1`, `PLANE_2`) it is lit in.
+    pub screen: [u8; SCREEN_PIXELS],
+    pub hires: bool,
+
+    /// Planes selected by `FN01` for drawing
+    pub planes: u8,
+
+    /// HEX based keypad (0x0-0xF)
+    pub key_states: [bool; KEYS_COUNT],
+
+    /// Timer registers
+    pub delay_timer: u8,
+    pub sound_timer: u8,
+
+    /// XO-CHIP audio pattern and pitch played while the sound timer runs
+    pub sound: Sound,
+
+    /// Stack and stack pointer
+    pub stack: [u16; STACK_SIZE],
+    pub sp: usize,
+
+    pub draw_flag: bool,
+
+    /// Memory written by the last instruction, as start address and length
+    pub last_write: Option<(usize, usize)>,
+
+    /// SUPER-CHIP RPL user flags
+    pub rpl: [u8; RPL_FLAGS_COUNT],
+
+    /// Behaviour of the ambiguous instructions
+    pub quirks: Quirks,
+
+    /// SHA-1 of the loaded ROM, identifies the game of a save state
+    pub rom_hash: [u8; 20],
+
+    /// Random number generator of `CXNN`
+    pub rng: StdRng,
+}
+
+impl Vm {
+
+    /// Creates a new `Vm` instance with default state
+    pub fn init() -> Vm {
+        Vm {
+            pc: PROGRAM_START,
+            i: 0,
+            opcode: 0,
+            v: [0; DATA_REGISTERS_COUNT],
+            ram: [0; RAM_SIZE],
+
+            screen: [0; SCREEN_PIXELS],
+            hires: false,
+            planes: PLANE_1,
+            key_states: [false; KEYS_COUNT],
+
+            stack: [0; STACK_SIZE],
+            sp: 0,
+
+            delay_timer: 0,
+            sound_timer: 0,
+
+            sound: Sound::default(),
+
+            draw_flag: false,
+
+            last_write: None,
+
+            rpl: [0; RPL_FLAGS_COUNT],
+
+            quirks: Quirks::default(),
+
+            rom_hash: [0; 20],
+
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    /// Makes `CXNN` produce the same numbers on every run
+    pub fn seed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    pub fn load_font(&mut self) {
+        self.ram[FONT_START..FONT_START + FONT_BYTES].copy_from_slice(&FONT);
+        self.ram[BIG_FONT_START..BIG_FONT_START + BIG_FONT_BYTES].copy_from_slice(&BIG_FONT);
+    }
+
+    /// Width of the screen in the active display mode
+    pub fn screen_width(&self) -> usize {
+        if self.hires { HIRES_SCREEN_WIDTH } else { SCREEN_WIDTH }
+    }
+
+    /// Height of the screen in the active display mode
+    pub fn screen_height(&self) -> usize {
+        if self.hires { HIRES_SCREEN_HEIGHT } else { SCREEN_HEIGHT }
+    }
+
+    /// Reads the ROM at `game_location` and loads it into RAM
+    pub fn load_game(&mut self, game_location: &str) -> Result<(), LoadError> {
+        let rom = loader::read_rom(game_location)?;
+        self.load_rom(&rom)
+    }
+
+    /// Loads the ROM image into RAM at the program start address
+    pub fn load_rom(&mut self, rom: &[u8]) -> Result<(), LoadError> {
+        let max = RAM_SIZE - PROGRAM_START;
+        if rom.len() > max {
+            return Err(LoadError::TooLarge { size: rom.len(), max });
+        }
+
+        self.ram[PROGRAM_START..PROGRAM_START + rom.len()].copy_from_slice(rom);
+        self.rom_hash = Sha1::from(rom).digest().bytes();
+        Ok(())
+    }
+
+    /// Fails with `MemoryOutOfBounds` unless `len` bytes starting
+    /// at `addr` are inside of the RAM
+    pub fn check_ram(&self, addr: usize, len: usize) -> Result<(), VmError> {
+        if addr + len > RAM_SIZE {
+            return Err(VmError::MemoryOutOfBounds { addr: addr.max(RAM_SIZE) });
+        }
+        Ok(())
+    }
+
+    fn unknown_opcode(&self) -> VmError {
+        VmError::UnknownOpcode { pc: self.pc, opcode: self.opcode }
+    }
+
+
+    pub fn translate_opcode(&mut self) -> Result<CycleOutcome, VmError> {
+
+        match self.opcode & 0xF000 {
+            0x0000 => match self.opcode & 0x0FFF {
+
+                // 00CN
+                0x00C0..=0x00CF => scd_n(self),
+                // 00DN
+                0x00D0..=0x00DF => scu_n(self),
+                // 00E0
+                0x00E0 => cls(self),
+                // 00EE
+                0x00EE => ret(self)?,
+                // 00FB
+                0x00FB => scr(self),
+                // 00FC
+                0x00FC => scl(self),
+                // 00FD
+                0x00FD => return Ok(exit(self)),
+                // 00FE
+                0x00FE => low(self),
+                // 00FF
+                0x00FF => high(self),
+
+                _ => return Err(self.unknown_opcode())
+            },
+
+            // 1NNN
+            0x1000 => jp_addr(self),
+
+            // 2NNN
+            0x2000 => call_addr(self)?,
+
+            // 3XNN
+            0x3000 => se_vx_byte(self),
+
+            // 4XNN
+            0x4000 => sne_vx_byte(self),
+
+            0x5000 => match self.opcode & 0x000F {
+
+                // 5XY0
+                0x0000 => se_vx_vy(self),
+
+                // 5XY2
+                0x0002 => save_vx_vy(self)?,
+
+                // 5XY3
+                0x0003 => load_vx_vy(self)?,
+
+                _ => return Err(self.unknown_opcode())
+            },
+
+            // 6XNN
+            0x6000 => ld_vx_byte(self),
+
+            // 7XNN
+            0x7000 => add_vx_byte(self),
+
+            0x8000 => match self.opcode & 0x000F {
+
+                // 8XY0
+                0x0000 => ld_vx_vy(self),
+
+                // 8XY1
+                0x0001 => or_vx_vy(self),
+
+                // 8XY2
+                0x0002 => and_vx_vy(self),
+
+                // 8XY3
+                0x0003 => xor_vx_vy(self),
+
+                // 8XY4
+                0x0004 => add_vx_vy(self),
+
+                // 8XY5
+                0x0005 => sub_vx_vy(self),
+
+                // 8XY6
+                0x0006 => shr_vx_vy(self),
+
+                // 8XY7
+                0x0007 => subn_vx_vy(self),
+
+                // 8XYE
+                0x000E => shl_vx_vy(self),
+
+                _ => return Err(self.unknown_opcode())
+            },
+
+            // 9XY0
+            0x9000 if self.opcode & 0x000F == 0 => sne_vx_vy(self),
+
+            // ANNN
+            0xA000 => ld_i_addr(self),
+
+            // BNNN
+            0xB000 => jp_v0_addr(self),
+
+            // CXNN
+            0xC000 => rnd_vx_byte(self),
+
+            // DXYN, DXY0
+            0xD000 => drw_vx_vy_n(self)?,
+
+            0xE000 => match self.opcode & 0x00FF {
+
+                // EX9E
+                0x009E => skp_vx(self)?,
+
+                // EXA1
+                0x00A1 => sknp_vx(self)?,
+
+                _ => return Err(self.unknown_opcode())
+            },
+
+            0xF000 => match self.opcode & 0x00FF {
+
+                // F000 NNNN
+                0x0000 if self.opcode == 0xF000 => ld_i_long(self)?,
+
+                // FN01
+                0x0001 => plane_n(self),
+
+                // F002
+                0x0002 if self.opcode == 0xF002 => audio(self)?,
+
+                // FX07
+                0x0007 => ld_vx_dt(self),
+
+                // FX0A
+                0x000A => return Ok(ld_vx_k(self)),
+
+                // FX15
+                0x0015 => ld_dt_vx(self),
+
+                // FX18
+                0x0018 => ld_st_vx(self),
+
+                // FX1E
+                0x001E => add_i_vx(self),
+
+                // FX29
+                0x0029 => ld_f_vx(self),
+
+                // FX30
+                0x0030 => ld_hf_vx(self),
+
+                // FX3A
+                0x003A => pitch_vx(self),
+
+                // FX33
+                0x0033 => ld_b_vx(self)?,
+
+                // FX55
+                0x0055 => ld_i_vx(self)?,
+
+                // FX65
+                0x0065 => ld_vx_i(self)?,
+
+                // FX75
+                0x0075 => ld_r_vx(self),
+
+                // FX85
+                0x0085 => ld_vx_r(self),
+
+                _ => return Err(self.unknown_opcode())
+            },
+
+            _ => return Err(self.unknown_opcode()),
+        };
+
+        Ok(CycleOutcome::Executed)
+    }
+
+    /// Ticks the delay and sound timers, once per 60 Hz frame
+    pub fn update_timers<F: Frontend>(&mut self, frontend: &mut F) {
+        if self.delay_timer > 0 {
+            self.delay_timer -= 1;
+        }
+        if self.sound_timer > 0 {
+            // keep the frontend up to date, the pattern may change while playing
+            frontend.play_sound(&self.sound);
+            self.sound_timer -= 1;
+        } else {
+            frontend.stop_sound();
+        }
+    }
+
+    /// Fetches and executes one instruction, without touching the timers
+    pub fn emulate_cycle(&mut self) -> Result<CycleOutcome, VmError> {
+        self.check_ram(self.pc, 2)?;
+
+        // fetch opcode: merge two memory locations for an opcode (build opcode with next two bytes)
+        self.opcode = (self.ram[self.pc] as u16) << 8 | self.ram[self.pc + 1] as u16;
+        self.last_write = None;
+
+        self.translate_opcode()
+    }
+
+    /// Runs one 60 Hz frame: executes `cycles` instructions, then ticks
+    /// the timers once
+    ///
+    /// Stops early, without ticking the timers, when the ROM exits or the
+    /// machine faults.
+    pub fn run_frame<F: Frontend>(&mut self, cycles: usize, frontend: &mut F) -> Result<CycleOutcome, VmError> {
+        self.run_frame_with(cycles, frontend, |_| {})
+    }
+
+    /// Like `run_frame`, calling `before_cycle` ahead of every instruction
+    pub fn run_frame_with<F, C>(&mut self, cycles: usize, frontend: &mut F, mut before_cycle: C)
+        -> Result<CycleOutcome, VmError>
+        where F: Frontend, C: FnMut(&Vm) {
+        let outcome = self.run_cycles_with(cycles, &mut before_cycle)?;
+        if outcome != CycleOutcome::Exit {
+            self.update_timers(frontend);
+        }
+        Ok(outcome)
+    }
+
+    /// Executes `cycles` instructions without ticking the timers, calling
+    /// `before_cycle` ahead of every instruction; stops early when the ROM
+    /// exits or the machine faults
+    pub fn run_cycles_with<C: FnMut(&Vm)>(&mut self, cycles: usize, mut before_cycle: C)
+        -> Result<CycleOutcome, VmError> {
+        let mut outcome = CycleOutcome::Executed;
+        for _ in 0..cycles {
+            before_cycle(self);
+            outcome = self.emulate_cycle()?;
+            if outcome == CycleOutcome::Exit {
+                return Ok(outcome);
+            }
+        }
+        Ok(outcome)
+    }
+}
\ No newline at end of file
@@ -0,0 +1,124 @@
+//! Conformance suite
+//!
+//! Runs the test ROMs of `tests/roms`, assembled with our own assembler,
+//! for a number of frames and compares the screen against the golden
+//! bitmaps of `tests/golden`, written as `Frame::to_ascii` text. The test
+//! ROMs draw a tick for every check that passes and a cross for every one
+//! that fails.
+//!
+//! Run with `UPDATE_GOLDEN=1` to write the golden bitmaps from the
+//! current output instead, then review the diff.
+
+extern crate chip8_core;
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use chip8_core::asm;
+use chip8_core::headless::{self, KeyScript, Limit, RunStatus};
+use chip8_core::quirks::PROFILES;
+use chip8_core::vm::CYCLES_PER_FRAME;
+use chip8_core::{Frame, NullFrontend, Quirks, Vm};
+
+/// Frames every ROM runs for, plenty for all of them to finish
+const FRAMES: u64 = 120;
+
+/// Key input of `keypad.asm`
+const KEYPAD_SCRIPT: &str = "
+2   down 5
+4   up   5
+8   down 7
+12  up   7
+";
+
+fn path(dir: &str, file: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join(dir).join(file)
+}
+
+/// Runs the test ROM `name` and returns its screen as text
+fn run(name: &str, quirks: Quirks, script: &KeyScript) -> String {
+    let rom = asm::assemble_file(path("roms", &format!("{}.asm", name)))
+        .unwrap_or_else(|why| panic!("couldn't assemble {}: {}", name, why));
+
+    let mut vm = Vm::init();
+    vm.load_font();
+    vm.quirks = quirks;
+    vm.seed(0);
+    vm.load_rom(&rom).unwrap();
+
+    let report = headless::run(&mut vm, Limit::Frames(FRAMES), CYCLES_PER_FRAME, script,
+                               &mut NullFrontend, None);
+    assert_eq!(report.status, RunStatus::Completed, "{} stopped early", name);
+    Frame::capture(&vm).to_ascii()
+}
+
+/// Compares the screen with the golden bitmap `golden`
+fn check(golden: &str, screen: &str, context: &str) {
+    let golden_path = path("golden", &format!("{}.txt", golden));
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::write(&golden_path, screen).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&golden_path)
+        .unwrap_or_else(|why| panic!("couldn't read {}: {}", golden_path.display(), why));
+    assert!(screen == expected, "{} differs from {} ({}):\n{}", golden, golden_path.display(), context, screen);
+}
+
+/// Runs a ROM meant to give the same screen under every quirks profile
+fn check_all_profiles(name: &str, script: &KeyScript) {
+    for profile in PROFILES.iter() {
+        let screen = run(name, Quirks::from_name(profile).unwrap(), script);
+        check(name, &screen, profile);
+    }
+}
+
+#[test]
+fn ibm_logo() {
+    check_all_profiles("ibm_logo", &KeyScript::default());
+}
+
+#[test]
+fn opcodes() {
+    check_all_profiles("opcodes", &KeyScript::default());
+}
+
+#[test]
+fn flags() {
+    check_all_profiles("flags", &KeyScript::default());
+}
+
+#[test]
+fn bc() {
+    check_all_profiles("bc", &KeyScript::default());
+}
+
+#[test]
+fn keypad() {
+    check_all_profiles("keypad", &KeyScript::parse(KEYPAD_SCRIPT).unwrap());
+}
+
+fn check_quirks(profile: &str) {
+    let screen = run("quirks", Quirks::from_name(profile).unwrap(), &KeyScript::default());
+    check(&format!("quirks_{}", profile), &screen, profile);
+}
+
+#[test]
+fn quirks_vip() {
+    check_quirks("vip");
+}
+
+#[test]
+fn quirks_schip() {
+    check_quirks("schip");
+}
+
+#[test]
+fn quirks_xochip() {
+    check_quirks("xochip");
+}
+
+#[test]
+fn quirks_modern() {
+    check_quirks("modern");
+}
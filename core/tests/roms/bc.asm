@@ -0,0 +1,90 @@
+; Miscellaneous test, in the spirit of BestCoder's BC_test
+;
+; 1. 00E0 clears the screen
+; 2. nested calls twelve deep return in order
+; 3. the delay timer counts down to zero
+; 4. DXYN reports collisions in VF
+; 5. 8XY3 of a register with itself gives zero
+; 6. FX1E adds VX to I
+
+        LD VB, 0
+        LD VC, 0
+
+        ; 00E0 clears the screen, so nothing collides afterwards
+        LD VA, 0
+        LD I, block
+        LD V0, 40
+        LD V1, 20
+        DRW V0, V1, 2
+        CLS
+        DRW V0, V1, 2
+        SNE VF, 0
+        LD VA, 1
+        DRW V0, V1, 2
+        CALL mark
+
+        ; nested calls, V0 counts the returns
+        LD VA, 0
+        LD V0, 0
+        LD V1, 12
+        CALL nest
+        SNE V0, 12
+        LD VA, 1
+        CALL mark
+
+        ; delay timer
+        LD VA, 0
+        LD V0, 3
+        LD DT, V0
+wait:   LD V0, DT
+        SE V0, 0
+        JP wait
+        LD VA, 1
+        CALL mark
+
+        ; collisions
+        LD VA, 0
+        LD I, block
+        LD V0, 40
+        LD V1, 20
+        DRW V0, V1, 2
+        SE VF, 0
+        JP t_collision
+        DRW V0, V1, 2
+        SNE VF, 1
+        LD VA, 1
+t_collision:
+        CALL mark
+
+        ; XOR with itself
+        LD VA, 0
+        LD V0, 0x5A
+        XOR V0, V0
+        SNE V0, 0
+        LD VA, 1
+        CALL mark
+
+        ; FX1E
+        LD VA, 0
+        LD I, bytes
+        LD V0, 2
+        ADD I, V0
+        LD V0, [I]
+        SNE V0, 0x77
+        LD VA, 1
+        CALL mark
+
+end:    JP end
+
+; Calls itself V1 times, counting the returns in V0
+nest:   ADD V1, 0xFF
+        SE V1, 0
+        CALL nest
+        ADD V0, 1
+        RET
+
+block:  DB 0xF0, 0xF0
+
+bytes:  DB 0x00, 0x00, 0x77
+
+include "mark.inc"
@@ -0,0 +1,120 @@
+; Flags test, in the spirit of Timendus' flags test
+;
+; VF after the arithmetic instructions, including the edge cases and VF
+; used as an operand: the flag is written after the result, so it wins
+; when VF is the destination.
+
+        LD VB, 0
+        LD VC, 0
+
+        ; 8XY4 sets VF on carry only
+        LD VA, 0
+        LD V0, 0x80
+        LD V1, 0x80
+        ADD V0, V1
+        SE VF, 1
+        JP t_add
+        LD V0, 0x7F
+        ADD V0, V1
+        SNE VF, 0
+        LD VA, 1
+t_add:  CALL mark
+
+        ; 8XY5 sets VF when there's no borrow, equal values included
+        LD VA, 0
+        LD V0, 0x20
+        LD V1, 0x20
+        SUB V0, V1
+        SE VF, 1
+        JP t_sub
+        LD V1, 0x21
+        SUB V0, V1
+        SNE VF, 0
+        LD VA, 1
+t_sub:  CALL mark
+
+        ; 8XY7 sets VF when there's no borrow, equal values included
+        LD VA, 0
+        LD V0, 0x20
+        LD V1, 0x20
+        SUBN V0, V1
+        SE VF, 1
+        JP t_subn
+        LD V0, 0x21
+        LD V1, 0x20
+        SUBN V0, V1
+        SNE VF, 0
+        LD VA, 1
+t_subn: CALL mark
+
+        ; 8XY6 / 8XYE shift the lost bit into VF
+        LD VA, 0
+        LD V0, 0x01
+        SHR V0, V0
+        SE VF, 1
+        JP t_shift
+        LD V0, 0x80
+        SHL V0, V0
+        SE VF, 1
+        JP t_shift
+        LD V0, 0x40
+        SHL V0, V0
+        SNE VF, 0
+        LD VA, 1
+t_shift:
+        CALL mark
+
+        ; VF as the destination of 8XY4 keeps the flag
+        LD VA, 0
+        LD VF, 0x10
+        LD V1, 0x20
+        ADD VF, V1
+        SNE VF, 0
+        LD VA, 1
+        CALL mark
+
+        ; VF as the destination of 8XY5 keeps the flag
+        LD VA, 0
+        LD VF, 0x30
+        LD V1, 0x10
+        SUB VF, V1
+        SNE VF, 1
+        LD VA, 1
+        CALL mark
+
+        ; VF as the destination of 8XY7 keeps the flag
+        LD VA, 0
+        LD VF, 0x10
+        LD V1, 0x30
+        SUBN VF, V1
+        SNE VF, 1
+        LD VA, 1
+        CALL mark
+
+        ; VF as the destination of the shifts keeps the flag
+        LD VA, 0
+        LD VF, 0x04
+        SHR VF, VF
+        SE VF, 0
+        JP t_vf_shift
+        LD VF, 0x81
+        SHL VF, VF
+        SNE VF, 1
+        LD VA, 1
+t_vf_shift:
+        CALL mark
+
+        ; VF as the source operand is read before it is overwritten
+        LD VA, 0
+        LD V0, 0xFF
+        LD VF, 1
+        ADD V0, VF
+        SNE V0, 0
+        LD VA, 1
+        SE VF, 1
+        LD VA, 0
+        CALL mark
+
+end:    JP end
+
+include "mark.inc"
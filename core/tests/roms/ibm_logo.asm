@@ -0,0 +1,27 @@
+; IBM logo, an equivalent of the classic first test ROM: 00E0, ANNN,
+; 6XNN, 7XNN, DXYN and 1NNN only
+
+        CLS
+        LD V0, 16
+        LD V1, 8
+        LD I, letter_i
+        DRW V0, V1, 15
+        ADD V0, 11
+        LD I, letter_b
+        DRW V0, V1, 15
+        ADD V0, 11
+        LD I, letter_m
+        DRW V0, V1, 15
+end:    JP end
+
+letter_i:
+        DB 0xFF, 0x00, 0x3C, 0x00, 0x3C, 0x00, 0x3C, 0x00
+        DB 0x3C, 0x00, 0x3C, 0x00, 0x3C, 0x00, 0xFF
+
+letter_b:
+        DB 0xFE, 0x00, 0xC3, 0x00, 0xC3, 0x00, 0xFE, 0x00
+        DB 0xC3, 0x00, 0xC3, 0x00, 0xC3, 0x00, 0xFE
+
+letter_m:
+        DB 0xC3, 0x00, 0xE7, 0x00, 0xFF, 0x00, 0xDB, 0x00
+        DB 0xC3, 0x00, 0xC3, 0x00, 0xC3, 0x00, 0xC3
@@ -0,0 +1,32 @@
+; Keypad test, driven by the key script of the test:
+; key 5 goes down and up, then key 7 is held for a few frames.
+;
+; 1. FX0A returns the key
+; 2. EX9E skips once key 7 is pressed
+; 3. EXA1 skips once key 7 is released
+
+        LD VB, 0
+        LD VC, 0
+
+        LD VA, 0
+        LD V0, K
+        SNE V0, 5
+        LD VA, 1
+        CALL mark
+
+        LD V1, 7
+wait_down:
+        SKP V1
+        JP wait_down
+        LD VA, 1
+        CALL mark
+
+wait_up:
+        SKNP V1
+        JP wait_up
+        LD VA, 1
+        CALL mark
+
+end:    JP end
+
+include "mark.inc"
@@ -0,0 +1,226 @@
+; Opcode test, in the spirit of corax89's chip8-test-rom
+;
+; One mark per instruction: 3XNN, 4XNN, 5XY0, 7XNN, 9XY0, 2NNN/00EE,
+; 8XY0 to 8XY3, 8XY4, 8XY5, 8XY7, 8XY6, 8XYE, FX1E, FX55/FX65, FX33,
+; BNNN, ANNN. Shifts use the same register as source and destination so
+; the result doesn't depend on the quirks profile.
+
+        LD VB, 0
+        LD VC, 0
+
+        ; 3XNN skips when equal
+        LD VA, 0
+        LD V0, 0x2A
+        SE V0, 0x2A
+        JP t_3xnn
+        LD VA, 1
+t_3xnn: CALL mark
+
+        ; 4XNN skips when different
+        LD VA, 0
+        SNE V0, 0x2B
+        JP t_4xnn
+        LD VA, 1
+t_4xnn: CALL mark
+
+        ; 5XY0 skips when the registers are equal
+        LD VA, 0
+        LD V1, 0x2A
+        SE V0, V1
+        JP t_5xy0
+        LD VA, 1
+t_5xy0: CALL mark
+
+        ; 7XNN wraps around without touching VF
+        LD VA, 0
+        LD VF, 7
+        LD V0, 0xFF
+        ADD V0, 2
+        SNE V0, 1
+        LD VA, 1
+        SE VF, 7
+        LD VA, 0
+        CALL mark
+
+        ; 9XY0 skips when the registers are different
+        LD VA, 0
+        LD V1, 0x2B
+        SNE V0, V1
+        JP t_9xy0
+        LD VA, 1
+t_9xy0: CALL mark
+
+        ; 2NNN / 00EE
+        LD VA, 0
+        CALL set_va
+        CALL mark
+
+        ; 8XY0, 8XY1, 8XY2, 8XY3
+        LD VA, 0
+        LD V1, 0x5A
+        LD V0, V1
+        LD V2, 0x0F
+        OR V1, V2
+        LD V3, 0x3C
+        AND V3, V2
+        LD V4, 0xFF
+        XOR V4, V2
+        SNE V0, 0x5A
+        LD VA, 1
+        SE V1, 0x5F
+        LD VA, 0
+        SE V3, 0x0C
+        LD VA, 0
+        SE V4, 0xF0
+        LD VA, 0
+        CALL mark
+
+        ; 8XY4 with and without carry
+        LD VA, 0
+        LD V0, 0xF0
+        LD V1, 0x20
+        ADD V0, V1
+        LD V2, VF
+        LD V3, 0x10
+        ADD V3, V1
+        SNE V0, 0x10
+        LD VA, 1
+        SE V2, 1
+        LD VA, 0
+        SE V3, 0x30
+        LD VA, 0
+        SE VF, 0
+        LD VA, 0
+        CALL mark
+
+        ; 8XY5 with and without borrow
+        LD VA, 0
+        LD V0, 0x10
+        LD V1, 0x20
+        SUB V0, V1
+        LD V2, VF
+        LD V3, 0x30
+        SUB V3, V1
+        SNE V0, 0xF0
+        LD VA, 1
+        SE V2, 0
+        LD VA, 0
+        SE V3, 0x10
+        LD VA, 0
+        SE VF, 1
+        LD VA, 0
+        CALL mark
+
+        ; 8XY7 with and without borrow
+        LD VA, 0
+        LD V0, 0x20
+        LD V1, 0x10
+        SUBN V0, V1
+        LD V2, VF
+        LD V3, 0x10
+        LD V1, 0x30
+        SUBN V3, V1
+        SNE V0, 0xF0
+        LD VA, 1
+        SE V2, 0
+        LD VA, 0
+        SE V3, 0x20
+        LD VA, 0
+        SE VF, 1
+        LD VA, 0
+        CALL mark
+
+        ; 8XY6
+        LD VA, 0
+        LD V0, 0x05
+        SHR V0, V0
+        SNE V0, 0x02
+        LD VA, 1
+        SE VF, 1
+        LD VA, 0
+        CALL mark
+
+        ; 8XYE
+        LD VA, 0
+        LD V0, 0x81
+        SHL V0, V0
+        SNE V0, 0x02
+        LD VA, 1
+        SE VF, 1
+        LD VA, 0
+        CALL mark
+
+        ; FX1E
+        LD VA, 0
+        LD I, scratch
+        LD V0, 2
+        ADD I, V0
+        LD V0, [I]
+        SNE V0, 0xCC
+        LD VA, 1
+        CALL mark
+
+        ; FX55 / FX65 round trip
+        LD VA, 0
+        LD V0, 0x11
+        LD V1, 0x22
+        LD V2, 0x33
+        LD I, scratch
+        LD [I], V2
+        LD V0, 0
+        LD V1, 0
+        LD V2, 0
+        LD I, scratch
+        LD V2, [I]
+        SNE V2, 0x33
+        LD VA, 1
+        SE V1, 0x22
+        LD VA, 0
+        SE V0, 0x11
+        LD VA, 0
+        CALL mark
+
+        ; FX33
+        LD VA, 0
+        LD V3, 219
+        LD I, scratch
+        LD B, V3
+        LD V2, [I]
+        SNE V0, 2
+        LD VA, 1
+        SE V1, 1
+        LD VA, 0
+        SE V2, 9
+        LD VA, 0
+        CALL mark
+
+        ; BNNN, with the same offset in the registers BXNN could pick
+        LD VA, 0
+        LD V0, 4
+        LD V2, 4
+        LD V3, 4
+        JP V0, t_bnnn
+t_bnnn: JP t_bnnn_out
+        JP t_bnnn_out
+        LD VA, 1
+t_bnnn_out:
+        CALL mark
+
+        ; FX29 points at the font
+        LD VA, 0
+        LD V0, 0x1A
+        LD F, V0
+        LD V0, [I]
+        SNE V0, 0xF0
+        LD VA, 1
+        CALL mark
+
+end:    JP end
+
+set_va: LD VA, 1
+        RET
+
+scratch:
+        DB 0xAA, 0xBB, 0xCC, 0xDD
+
+include "mark.inc"
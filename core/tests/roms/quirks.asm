@@ -0,0 +1,73 @@
+; Quirks test, in the spirit of Timendus' quirks test
+;
+; One mark per quirk, a tick when the quirk is on:
+; 1. 8XY6 / 8XYE shift VY
+; 2. FX55 / FX65 increment I
+; 3. BNNN jumps to XNN plus VX
+; 4. 8XY1 / 8XY2 / 8XY3 reset VF
+; 5. DXYN clips sprites at the edges of the screen
+
+        LD VB, 0
+        LD VC, 0
+
+        ; shift quirk
+        LD VA, 0
+        LD V0, 0x10
+        LD V1, 0x04
+        SHR V0, V1
+        SNE V0, 0x02
+        LD VA, 1
+        CALL mark
+
+        ; load/store quirk: the second load reads the next byte
+        LD VA, 0
+        LD V0, 0x11
+        LD I, scratch
+        LD [I], V0
+        LD V0, [I]
+        SNE V0, 0x22
+        LD VA, 1
+        CALL mark
+
+        ; jump quirk: VX (V2 or V3 for addresses in 0x200 - 0x3FF)
+        ; skips one instruction, V0 doesn't
+        LD VA, 0
+        LD V0, 0
+        LD V2, 2
+        LD V3, 2
+        JP V0, t_jump
+t_jump: JP t_jump_out
+        LD VA, 1
+t_jump_out:
+        CALL mark
+
+        ; logic quirk
+        LD VA, 0
+        LD VF, 5
+        OR V0, V1
+        SNE VF, 0
+        LD VA, 1
+        CALL mark
+
+        ; clipping quirk: a wrapped sprite hits the pixels at the left edge
+        LD VA, 0
+        LD I, line
+        LD V0, 0
+        LD V1, 20
+        LD V2, 62
+        DRW V0, V1, 1
+        DRW V2, V1, 1
+        SNE VF, 0
+        LD VA, 1
+        DRW V2, V1, 1
+        DRW V0, V1, 1
+        CALL mark
+
+end:    JP end
+
+line:   DB 0xFF
+
+scratch:
+        DB 0x00, 0x22
+
+include "mark.inc"
@@ -0,0 +1,269 @@
+//! Configuration file
+//!
+//! Read from `$XDG_CONFIG_HOME/chip-8/config.toml`, falling back to
+//! `~/.config/chip-8/config.toml`. Every section is optional:
+//!
+//! ```toml
+//! [defaults]
+//! scale = 12
+//! cycles_per_frame = 15
+//! quirks = "modern"
+//! palette = "amber"            # or colours, such as "101010,33ff66"
+//! volume = 40
+//! filter = "decay"              # off, decay or two-frame
+//! decay = 60                    # intensity kept per frame, in percent
+//!
+//! # keypad key = host keys, named as in SDL, or physical key
+//! # positions prefixed with "scan:"
+//! [keys]
+//! 5 = ["scan:W", "Up"]
+//!
+//! # controller button or axis direction = keypad key
+//! [controller]
+//! dpup = "5"
+//! "leftx-" = "7"
+//!
+//! # overrides for the ROM with this SHA-1
+//! [rom.0123456789abcdef0123456789abcdef01234567]
+//! cycles_per_frame = 30
+//! quirks = "schip"
+//!
+//! [rom.0123456789abcdef0123456789abcdef01234567.controller]
+//! a = "F"
+//! ```
+//!
+//! Command line options take precedence over the ROM section, which takes
+//! precedence over the defaults.
+
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use chip8_core::filter::FILTER_MODES;
+use chip8_core::quirks::PROFILES;
+use chip8_core::vm::KEYS_COUNT;
+use chip8_core::{Palette, Quirks};
+
+use crate::controller::{Control, ControllerMap};
+use crate::keymap::{HostKey, KeyMap};
+
+/// Settings that can be given on the command line, in the defaults or in
+/// a ROM section; `None` when not given
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Settings {
+    pub scale: Option<u32>,
+    pub cycles_per_frame: Option<usize>,
+    pub quirks: Option<Quirks>,
+    pub palette: Option<Palette>,
+    /// Volume in percent
+    pub volume: Option<u32>,
+    /// Name of the display filter, see `FILTER_MODES`
+    pub filter: Option<&'static str>,
+    /// Decay of the `decay` filter in percent
+    pub decay: Option<u32>,
+}
+
+impl Settings {
+    /// Takes the settings missing here from `fallback`
+    pub fn or(self, fallback: Settings) -> Settings {
+        Settings {
+            scale: self.scale.or(fallback.scale),
+            cycles_per_frame: self.cycles_per_frame.or(fallback.cycles_per_frame),
+            quirks: self.quirks.or(fallback.quirks),
+            palette: self.palette.or(fallback.palette),
+            volume: self.volume.or(fallback.volume),
+            filter: self.filter.or(fallback.filter),
+            decay: self.decay.or(fallback.decay),
+        }
+    }
+}
+
+/// Parsed configuration file
+#[derive(Debug, Default)]
+pub struct Config {
+    pub defaults: Settings,
+    /// Default key bindings, with the keypad keys of the `[keys]` section rebound
+    pub keys: KeyMap,
+    /// Default controller bindings, with the `[controller]` section applied
+    controller: ControllerMap,
+    /// Overrides by lower case hex SHA-1 of the ROM
+    roms: HashMap<String, Settings>,
+    /// Controller bindings of the ROM sections, by lower case hex SHA-1
+    rom_controllers: HashMap<String, Vec<(Control, u8)>>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct SettingsFile {
+    scale: Option<u32>,
+    cycles_per_frame: Option<usize>,
+    quirks: Option<String>,
+    palette: Option<String>,
+    volume: Option<u32>,
+    filter: Option<String>,
+    decay: Option<u32>,
+    controller: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile {
+    defaults: SettingsFile,
+    keys: BTreeMap<String, Vec<String>>,
+    controller: BTreeMap<String, String>,
+    rom: BTreeMap<String, SettingsFile>,
+}
+
+/// Known filter name equal to `name`
+pub fn filter_name(name: &str) -> Option<&'static str> {
+    FILTER_MODES.iter().copied().find(|&mode| mode == name)
+}
+
+/// Parses a keypad key, a hex digit
+fn keypad_key(text: &str) -> Option<u8> {
+    u8::from_str_radix(text, 16).ok().filter(|&key| (key as usize) < KEYS_COUNT)
+}
+
+/// Parses the controller bindings of `section`
+fn controller_bindings(table: &BTreeMap<String, String>, section: &str) -> Result<Vec<(Control, u8)>, String> {
+    table.iter().map(|(name, key)| {
+        let control = Control::from_name(name)
+            .ok_or_else(|| format!("[{}]: unknown controller button or axis '{}'", section, name))?;
+        let key = keypad_key(key)
+            .ok_or_else(|| format!("[{}]: '{}' isn't a keypad key, expected 0 to F", section, key))?;
+        Ok((control, key))
+    }).collect()
+}
+
+impl SettingsFile {
+    fn parse(&self, section: &str) -> Result<Settings, String> {
+        let error = |message: String| format!("[{}]: {}", section, message);
+
+        if let Some(scale) = self.scale.filter(|scale| !(1..=64).contains(scale)) {
+            return Err(error(format!("scale {} isn't from 1 to 64", scale)));
+        }
+        if self.cycles_per_frame == Some(0) {
+            return Err(error("cycles_per_frame must be at least 1".to_string()));
+        }
+        if let Some(volume) = self.volume.filter(|&volume| volume > 100) {
+            return Err(error(format!("volume {} isn't from 0 to 100", volume)));
+        }
+        if let Some(decay) = self.decay.filter(|&decay| decay > 100) {
+            return Err(error(format!("decay {} isn't from 0 to 100", decay)));
+        }
+        let filter = match &self.filter {
+            Some(name) => Some(filter_name(name).ok_or_else(|| {
+                error(format!("unknown filter '{}', expected one of {}", name, FILTER_MODES.join(", ")))
+            })?),
+            None => None,
+        };
+        let quirks = match &self.quirks {
+            Some(name) => Some(Quirks::from_name(name).ok_or_else(|| {
+                error(format!("unknown quirks profile '{}', expected one of {}", name, PROFILES.join(", ")))
+            })?),
+            None => None,
+        };
+        let palette = match &self.palette {
+            Some(colors) => Some(Palette::parse(colors).map_err(|why| error(format!("invalid palette: {}", why)))?),
+            None => None,
+        };
+
+        Ok(Settings {
+            scale: self.scale,
+            cycles_per_frame: self.cycles_per_frame,
+            quirks,
+            palette,
+            volume: self.volume,
+            filter,
+            decay: self.decay,
+        })
+    }
+}
+
+/// Location of the configuration file
+pub fn default_path() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("chip-8").join("config.toml"))
+}
+
+/// Formats a SHA-1 as used in the ROM section names
+pub fn hex(hash: &[u8]) -> String {
+    hash.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+impl Config {
+    /// Reads the configuration file at `path`, a missing file being an
+    /// empty configuration
+    pub fn load(path: &Path) -> Result<Config, String> {
+        match fs::read_to_string(path) {
+            Ok(text) => Config::parse(&text),
+            Err(ref why) if why.kind() == io::ErrorKind::NotFound => Ok(Config::default()),
+            Err(why) => Err(why.to_string()),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Config, String> {
+        let file: ConfigFile = toml::from_str(text).map_err(|why| why.to_string())?;
+
+        let mut keys = KeyMap::default();
+        for (key, names) in file.keys {
+            let keypad_key = keypad_key(&key)
+                .ok_or_else(|| format!("[keys]: '{}' isn't a keypad key, expected 0 to F", key))?;
+            keys.unbind(keypad_key);
+            for name in names {
+                let host = HostKey::from_name(&name).ok_or_else(|| format!("[keys]: unknown key '{}'", name))?;
+                keys.bind(host, keypad_key);
+            }
+        }
+
+        let mut controller = ControllerMap::default();
+        for (control, key) in controller_bindings(&file.controller, "controller")? {
+            controller.bind(control, key);
+        }
+        if !file.defaults.controller.is_empty() {
+            return Err("[defaults]: controller bindings go into the [controller] section".to_string());
+        }
+
+        let mut roms = HashMap::new();
+        let mut rom_controllers = HashMap::new();
+        for (hash, settings) in file.rom {
+            let section = format!("rom.{}", hash);
+            let hash = hash.to_ascii_lowercase();
+            let bindings = controller_bindings(&settings.controller, &format!("{}.controller", section))?;
+            rom_controllers.insert(hash.clone(), bindings);
+            roms.insert(hash, settings.parse(&section)?);
+        }
+
+        Ok(Config {
+            defaults: file.defaults.parse("defaults")?,
+            keys,
+            controller,
+            roms,
+            rom_controllers,
+        })
+    }
+
+    /// Controller bindings for the ROM with the SHA-1 `rom_hash`
+    pub fn controller(&self, rom_hash: &[u8]) -> ControllerMap {
+        let mut map = self.controller.clone();
+        for &(control, key) in self.rom_controllers.get(&hex(rom_hash)).into_iter().flatten() {
+            map.bind(control, key);
+        }
+        map
+    }
+
+    /// Settings for the ROM with the SHA-1 `rom_hash`
+    pub fn settings(&self, rom_hash: &[u8]) -> Settings {
+        match self.roms.get(&hex(rom_hash)) {
+            Some(rom) => rom.or(self.defaults),
+            None => self.defaults,
+        }
+    }
+}
@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+
+use sdl2::controller::{Axis, Button, GameController};
+use sdl2::event::Event;
+use sdl2::{GameControllerSubsystem, Sdl};
+
+use chip8_core::Vm;
+
+/// Axis values closer to the centre than this are ignored
+pub const AXIS_DEADZONE: i16 = 8000;
+
+/// Suffixes of axis names for either direction, e.g. `leftx-` and `leftx+`
+const NEGATIVE: char = '-';
+const POSITIVE: char = '+';
+
+/// Bindings used unless the config changes them: the directions on the
+/// keypad keys of `W`, `A`, `S` and `D`, the face buttons on `Q` and `E`
+const DEFAULT_BINDINGS: [(&str, u8); 12] = [
+    ("dpup", 0x5), ("dpleft", 0x7), ("dpdown", 0x8), ("dpright", 0x9),
+    ("lefty-", 0x5), ("leftx-", 0x7), ("lefty+", 0x8), ("leftx+", 0x9),
+    ("a", 0x6), ("b", 0x4), ("x", 0x4), ("y", 0x6),
+];
+
+/// Button, or axis in one direction, of a game controller
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Control {
+    Button(Button),
+    /// Axis, pushed to its positive (`true`) or negative side
+    Axis(Axis, bool),
+}
+
+impl Control {
+    /// Parses an SDL button name such as `dpup` or `a`, or an axis name
+    /// followed by the direction such as `leftx-` or `triggerleft+`
+    pub fn from_name(name: &str) -> Option<Control> {
+        if let Some(axis) = name.strip_suffix(NEGATIVE) {
+            Axis::from_string(axis).map(|axis| Control::Axis(axis, false))
+        } else if let Some(axis) = name.strip_suffix(POSITIVE) {
+            Axis::from_string(axis).map(|axis| Control::Axis(axis, true))
+        } else {
+            Button::from_string(name).map(Control::Button)
+        }
+    }
+}
+
+/// Bindings of controller buttons and axes to the keys of the keypad
+#[derive(Debug, Clone)]
+pub struct ControllerMap {
+    bindings: HashMap<Control, u8>,
+}
+
+impl ControllerMap {
+    /// Binds `control` to the keypad `key`, replacing its previous binding
+    pub fn bind(&mut self, control: Control, key: u8) {
+        self.bindings.insert(control, key);
+    }
+
+    pub fn lookup(&self, control: Control) -> Option<u8> {
+        self.bindings.get(&control).copied()
+    }
+}
+
+impl Default for ControllerMap {
+    fn default() -> ControllerMap {
+        let bindings = DEFAULT_BINDINGS.iter()
+            .map(|&(name, key)| (Control::from_name(name).unwrap(), key))
+            .collect();
+        ControllerMap { bindings }
+    }
+}
+
+/// Connected game controllers, feeding their input to the keypad
+pub struct Controllers {
+    subsystem: GameControllerSubsystem,
+    /// Open controllers by joystick id
+    open: HashMap<i32, GameController>,
+    /// Side each axis is pushed to: -1, 0 or 1
+    axes: HashMap<(i32, Axis), i8>,
+    /// Keypad keys held by the buttons and axes of each controller
+    held: HashMap<(i32, Control), u8>,
+    map: ControllerMap,
+}
+
+impl Controllers {
+    /// Starts listening for controllers; those already plugged in are
+    /// reported by SDL as added right away
+    pub fn init(sdl_context: &Sdl, map: ControllerMap) -> Controllers {
+        Controllers {
+            subsystem: sdl_context.game_controller().unwrap(),
+            open: HashMap::new(),
+            axes: HashMap::new(),
+            held: HashMap::new(),
+            map,
+        }
+    }
+
+    fn set_key(&mut self, vm: &mut Vm, which: i32, control: Control, pressed: bool) {
+        if let Some(key) = self.map.lookup(control) {
+            vm.key_states[key as usize] = pressed;
+            if pressed {
+                self.held.insert((which, control), key);
+            } else {
+                self.held.remove(&(which, control));
+            }
+        }
+    }
+
+    /// Releases the keys held by controller `which`, unless another
+    /// controller holds them too
+    fn release_all(&mut self, vm: &mut Vm, which: i32) {
+        let (released, kept): (HashMap<_, _>, HashMap<_, _>) = self.held.drain()
+            .partition(|&((id, _), _)| id == which);
+        for key in released.into_values() {
+            if !kept.values().any(|&held| held == key) {
+                vm.key_states[key as usize] = false;
+            }
+        }
+        self.held = kept;
+    }
+
+    /// Handles controller events, ignoring any other event
+    pub fn handle(&mut self, event: &Event, vm: &mut Vm) {
+        match *event {
+            Event::ControllerDeviceAdded { which, .. } => {
+                match self.subsystem.open(which) {
+                    Ok(controller) => {
+                        println!("Controller connected: {}", controller.name());
+                        self.open.insert(controller.instance_id(), controller);
+                    }
+                    Err(why) => eprintln!("couldn't open controller {}: {}", which, why),
+                }
+            }
+            Event::ControllerDeviceRemoved { which, .. } => {
+                if let Some(controller) = self.open.remove(&which) {
+                    println!("Controller disconnected: {}", controller.name());
+                }
+                // release whatever the controller was holding
+                self.axes.retain(|&(id, _), _| id != which);
+                self.release_all(vm, which);
+            }
+            Event::ControllerButtonDown { which, button, .. } => self.set_key(vm, which, Control::Button(button), true),
+            Event::ControllerButtonUp { which, button, .. } => self.set_key(vm, which, Control::Button(button), false),
+            Event::ControllerAxisMotion { which, axis, value, .. } => {
+                let side = if value <= -AXIS_DEADZONE {
+                    -1
+                } else if value >= AXIS_DEADZONE {
+                    1
+                } else {
+                    0
+                };
+                let previous = self.axes.insert((which, axis), side).unwrap_or(0);
+                if side != previous {
+                    if previous != 0 {
+                        self.set_key(vm, which, Control::Axis(axis, previous > 0), false);
+                    }
+                    if side != 0 {
+                        self.set_key(vm, which, Control::Axis(axis, side > 0), true);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+
+use sdl2::keyboard::{Keycode, Scancode};
+
+use chip8_core::vm::KEYS_COUNT;
+
+/// Prefix of host key names that refer to a physical key position
+pub const SCANCODE_PREFIX: &str = "scan:";
+
+/// Keypad layout on the left of the keyboard, as physical key positions
+/// so it is the same on QWERTY, AZERTY or Dvorak keyboards:
+///
+/// ```text
+/// 1 2 3 C      1 2 3 4
+/// 4 5 6 D      Q W E R
+/// 7 8 9 E  ->  A S D F
+/// A 0 B F      Z X C V   (as printed on a QWERTY keyboard)
+/// ```
+pub const DEFAULT_LAYOUT: [(u8, Scancode); KEYS_COUNT] = [
+    (0x1, Scancode::Num1), (0x2, Scancode::Num2), (0x3, Scancode::Num3), (0xC, Scancode::Num4),
+    (0x4, Scancode::Q), (0x5, Scancode::W), (0x6, Scancode::E), (0xD, Scancode::R),
+    (0x7, Scancode::A), (0x8, Scancode::S), (0x9, Scancode::D), (0xE, Scancode::F),
+    (0xA, Scancode::Z), (0x0, Scancode::X), (0xB, Scancode::C), (0xF, Scancode::V),
+];
+
+/// Key of the host keyboard
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HostKey {
+    /// Key by the symbol it produces in the active keyboard layout
+    Keycode(Keycode),
+    /// Key by its physical position
+    Scancode(Scancode),
+}
+
+impl HostKey {
+    /// Parses an SDL key name such as `W` or `Up`, or a physical key
+    /// position prefixed with `scan:`, such as `scan:W`
+    pub fn from_name(name: &str) -> Option<HostKey> {
+        if let Some(position) = name.strip_prefix(SCANCODE_PREFIX) {
+            Scancode::from_name(position).map(HostKey::Scancode)
+        } else {
+            Keycode::from_name(name).map(HostKey::Keycode)
+        }
+    }
+}
+
+/// Bindings of host keys to the keys of the keypad
+///
+/// Any number of host keys can be bound to a keypad key. Keycodes are
+/// looked up first, so a binding by symbol wins over the physical
+/// position of the key.
+#[derive(Debug, Clone)]
+pub struct KeyMap {
+    keycodes: HashMap<Keycode, u8>,
+    scancodes: HashMap<Scancode, u8>,
+}
+
+impl KeyMap {
+    /// Creates a map without any bindings
+    pub fn empty() -> KeyMap {
+        KeyMap {
+            keycodes: HashMap::new(),
+            scancodes: HashMap::new(),
+        }
+    }
+
+    /// Binds `host` to the keypad `key`, in addition to its other host keys
+    pub fn bind(&mut self, host: HostKey, key: u8) {
+        match host {
+            HostKey::Keycode(keycode) => self.keycodes.insert(keycode, key),
+            HostKey::Scancode(scancode) => self.scancodes.insert(scancode, key),
+        };
+    }
+
+    /// Removes all host keys bound to the keypad `key`
+    pub fn unbind(&mut self, key: u8) {
+        self.keycodes.retain(|_, bound| *bound != key);
+        self.scancodes.retain(|_, bound| *bound != key);
+    }
+
+    /// Keypad key bound to a pressed or released host key
+    pub fn lookup(&self, keycode: Option<Keycode>, scancode: Option<Scancode>) -> Option<u8> {
+        keycode.and_then(|keycode| self.keycodes.get(&keycode))
+            .or_else(|| scancode.and_then(|scancode| self.scancodes.get(&scancode)))
+            .copied()
+    }
+}
+
+impl Default for KeyMap {
+    fn default() -> KeyMap {
+        let mut map = KeyMap::empty();
+        for &(key, scancode) in DEFAULT_LAYOUT.iter() {
+            map.bind(HostKey::Scancode(scancode), key);
+        }
+        map
+    }
+}
@@ -1,31 +1,240 @@
-extern crate rand;
+extern crate chip8_core;
 extern crate sdl2;
 
-use std::env;
-use std::time::Duration;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufWriter, Write};
+use std::path::Path;
+use std::process;
 
 use sdl2::event::Event;
-use sdl2::keyboard::Keycode;
+use sdl2::keyboard::{Keycode, Mod};
 
+use chip8_core::clock::FrameClock;
+use chip8_core::debugger::{self, Action, Debugger};
+use chip8_core::filter::DEFAULT_DECAY;
+use chip8_core::headless::{self, KeyScript, Limit, RunStatus};
+use chip8_core::rewind::Rewind;
+use chip8_core::trace::Tracer;
+use chip8_core::vm::CYCLES_PER_FRAME;
+use chip8_core::{CycleOutcome, Filter, FilterMode, Frame, Frontend, NullFrontend, Palette, Recorder, Vm};
+
+use crate::config::{Config, Settings};
+use crate::controller::Controllers;
+use crate::options::{Options, DEFAULT_SCALE, DEFAULT_VOLUME};
 use crate::ui::Ui;
-use crate::vm::Vm;
 
-mod vm;
+mod config;
+mod controller;
+mod keymap;
+mod options;
 mod ui;
-mod opcodes;
 
-static SCALE: u32 = 16;
+/// Reads debugger commands from stdin until one of them resumes the machine
+fn prompt(debugger: &mut Debugger, vm: &mut Vm) -> Action {
+    if let Some(reason) = debugger.take_stop_reason() {
+        println!("{}", reason);
+    }
+    print!("{}", debugger::registers(vm));
+
+    let stdin = io::stdin();
+    loop {
+        print!("(chip-8) ");
+        let _ = io::stdout().flush();
+
+        let mut line = String::new();
+        match stdin.lock().read_line(&mut line) {
+            Ok(0) | Err(_) => return Action::Quit,
+            Ok(_) => {}
+        }
+        let mut out = String::new();
+        let action = debugger.execute(&line, vm, &mut out);
+        print!("{}", out);
+        if action != Action::Prompt {
+            return action;
+        }
+    }
+}
+
+/// Hotkeys of the save state slots, Shift saves and the plain key loads
+const STATE_SLOT_KEYS: [Keycode; 4] = [Keycode::F1, Keycode::F2, Keycode::F3, Keycode::F4];
+
+/// Path of the save state `slot` of the game, next to the ROM
+fn state_path(game_location: &str, slot: usize) -> String {
+    format!("{}.state{}", game_location, slot)
+}
+
+fn save_slot(vm: &Vm, game_location: &str, slot: usize) {
+    let path = state_path(game_location, slot);
+    match fs::write(&path, vm.save_state()) {
+        Ok(()) => println!("Saved state {} to {}", slot, path),
+        Err(why) => eprintln!("couldn't save {}: {}", path, why),
+    }
+}
+
+/// Restores the save state `slot`, returns whether it was loaded
+fn load_slot(vm: &mut Vm, game_location: &str, slot: usize) -> bool {
+    let path = state_path(game_location, slot);
+    let loaded = fs::read(&path)
+        .map_err(|why| why.to_string())
+        .and_then(|state| vm.load_state(&state).map_err(|why| why.to_string()));
+    match loaded {
+        Ok(()) => {
+            println!("Loaded state {} from {}", slot, path);
+            true
+        }
+        Err(why) => {
+            eprintln!("couldn't load {}: {}", path, why);
+            false
+        }
+    }
+}
+
+/// Path of a new file next to the ROM, numbered after the existing ones,
+/// such as `game.ch8.rec1.png` for the kind `rec`
+fn new_path(game_location: &str, kind: &str) -> String {
+    (1..).map(|number| format!("{}.{}{}.png", game_location, kind, number))
+        .find(|path| !Path::new(path).exists())
+        .unwrap()
+}
+
+/// Writes the screen to a new PNG image next to the ROM
+fn save_screenshot(vm: &Vm, game_location: &str, palette: &Palette, scale: u32) {
+    let path = new_path(game_location, "shot");
+    let written = File::create(&path)
+        .and_then(|file| Frame::capture(vm).write_png(BufWriter::new(file), palette, scale as usize));
+    match written {
+        Ok(()) => println!("Saved screenshot to {}", path),
+        Err(why) => eprintln!("couldn't save {}: {}", path, why),
+    }
+}
+
+fn save_recording(recorder: &Recorder, path: &str) {
+    match recorder.save(path) {
+        Ok(()) => println!("Saved {} frames to {}", recorder.len(), path),
+        Err(why) => eprintln!("couldn't save {}: {}", path, why),
+    }
+}
+
+/// Runs the game without a window, prints the final screen and returns the exit status
+fn run_headless(vm: &mut Vm, options: &Options, settings: &Settings, mut tracer: Option<Tracer>) -> i32 {
+    let limit = Limit::Frames(options.frames);
+    let cycles_per_frame = settings.cycles_per_frame.unwrap_or(CYCLES_PER_FRAME);
+    let palette = settings.palette.unwrap_or_default();
+    let scale = settings.scale.unwrap_or(DEFAULT_SCALE);
+    let mut recorder = options.record.as_ref().map(|_| Recorder::new(palette, scale as usize));
+    let mut frames = 0;
+    let trace = |vm: &Vm, _| {
+        if let Some(tracer) = tracer.as_mut() {
+            tracer.record(vm);
+        }
+    };
+    let report = headless::run_with(vm, limit, cycles_per_frame, &KeyScript::default(), &mut NullFrontend,
+                                    trace, |vm| {
+        frames += 1;
+        if options.screenshot_after == Some(frames) {
+            save_screenshot(vm, &options.game_location, &palette, options.screenshot_scale.unwrap_or(scale));
+        }
+        if let Some(recorder) = recorder.as_mut() {
+            recorder.capture(vm);
+        }
+    });
+    print!("{}", Frame::capture(vm).to_ascii());
+    finish_trace(tracer, options);
+    if let (Some(recorder), Some(path)) = (recorder, options.record.as_ref()) {
+        save_recording(&recorder, path);
+    }
+
+    if let RunStatus::Fault(error) = report.status {
+        eprintln!("Fault at PC 0x{:03X} (opcode 0x{:04X}): {}", vm.pc, vm.opcode, error);
+        return 1;
+    }
+    0
+}
+
+fn finish_trace(tracer: Option<Tracer>, options: &Options) {
+    if let (Some(tracer), Some(path)) = (tracer, options.trace.as_ref()) {
+        if let Err(why) = tracer.finish() {
+            eprintln!("couldn't write {}: {}", path, why);
+        }
+    }
+}
 
 fn main() {
-    let sdl_context = sdl2::init().unwrap();
-    let args: Vec<String> = env::args().collect();
+    let options = Options::parse();
+    let game_location = &options.game_location;
+
+    let config = match options.config.clone().or_else(config::default_path) {
+        Some(path) => Config::load(&path).unwrap_or_else(|why| {
+            eprintln!("couldn't read {}: {}", path.display(), why);
+            process::exit(2);
+        }),
+        None => Config::default(),
+    };
+
     let mut vm = Vm::init();
     vm.load_font();
+    if let Some(seed) = options.seed {
+        vm.seed(seed);
+    }
+
+    if let Err(why) = vm.load_game(game_location) {
+        eprintln!("couldn't load {}: {}", game_location, why);
+        process::exit(1);
+    }
+
+    // the command line wins over the ROM's section, which wins over the defaults
+    let settings = options.settings.or(config.settings(&vm.rom_hash));
+    vm.quirks = settings.quirks.unwrap_or_default();
+    let cycles_per_frame = settings.cycles_per_frame.unwrap_or(CYCLES_PER_FRAME);
+    let volume = settings.volume.unwrap_or(DEFAULT_VOLUME) as f32 / 100.0;
+
+    let mut tracer = options.trace.as_ref().map(|path| {
+        let mut tracer = Tracer::create(path).unwrap_or_else(|why| {
+            eprintln!("couldn't create {}: {}", path, why);
+            process::exit(2);
+        });
+        tracer.set_filter(options.trace_filter.clone());
+        tracer.set_screen_hash(options.trace_screen);
+        tracer
+    });
+
+    if options.headless {
+        process::exit(run_headless(&mut vm, &options, &settings, tracer));
+    }
 
-    vm.load_game(&args[1]);
+    let sdl_context = sdl2::init().unwrap();
+    let mut controllers = Controllers::init(&sdl_context, config.controller(&vm.rom_hash));
+    let filter = settings.filter
+        .and_then(|name| FilterMode::from_name(name, settings.decay.unwrap_or(DEFAULT_DECAY)))
+        .unwrap_or_default();
+    let scale = settings.scale.unwrap_or(DEFAULT_SCALE);
+    let mut ui = Ui::init(&sdl_context, scale, settings.palette.unwrap_or_default(),
+                          Filter::new(filter), if options.mute { None } else { Some(volume) }, options.fullscreen,
+                          config.keys);
+
+    let mut debugger = if options.debug {
+        println!("Debugger enabled, the game starts paused. Press F8 in the window to break in, type 'help' for commands.");
+        Some(Debugger::new())
+    } else {
+        None
+    };
+
+    let screenshot_scale = options.screenshot_scale.unwrap_or(scale);
+    let mut frames = 0;
+
+    // recording in progress and the file it goes to
+    let mut recording = options.record.clone().map(|path| (Recorder::new(ui.palette(), scale as usize), path));
 
-    let mut ui = Ui::init(&sdl_context, SCALE);
+    let mut rewind = Rewind::new(options.rewind_depth);
+    let mut rewinding = false;
+    let mut clock = FrameClock::default();
+    let mut trace = |vm: &Vm| {
+        if let Some(tracer) = tracer.as_mut() {
+            tracer.record(vm);
+        }
+    };
 
+    let mut halted = false;
     let mut event_pump = sdl_context.event_pump().unwrap();
     'running: loop {
         for event in event_pump.poll_iter() {
@@ -33,15 +242,100 @@ fn main() {
                 Event::Quit { .. } | Event::KeyDown { keycode: Some(Keycode::Escape), .. } => {
                     break 'running;
                 }
-                Event::KeyDown { keycode: Some(keycode), .. } => ui.set_key_pressed(&mut vm, keycode),
-                Event::KeyUp { keycode: Some(keycode), .. } => ui.set_key_released(&mut vm, keycode),
-                _ => {}
+                Event::KeyDown { keycode: Some(Keycode::F8), .. } if debugger.is_some() => {
+                    debugger.as_mut().unwrap().pause();
+                }
+                Event::KeyDown { keycode: Some(keycode), keymod, repeat: false, .. }
+                    if STATE_SLOT_KEYS.contains(&keycode) => {
+                    let slot = STATE_SLOT_KEYS.iter().position(|&key| key == keycode).unwrap() + 1;
+                    if keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD) {
+                        save_slot(&vm, game_location, slot);
+                    } else {
+                        // a restored state resumes a machine halted by a fault
+                        halted &= !load_slot(&mut vm, game_location, slot);
+                    }
+                }
+                Event::KeyDown { keycode: Some(Keycode::Tab), repeat: false, .. } => ui.next_theme(&vm),
+                Event::KeyDown { keycode: Some(Keycode::F9), repeat: false, .. } => {
+                    save_screenshot(&vm, game_location, &ui.palette(), screenshot_scale);
+                }
+                Event::KeyDown { keycode: Some(Keycode::F10), repeat: false, .. } => {
+                    match recording.take() {
+                        Some((recorder, path)) => save_recording(&recorder, &path),
+                        None => {
+                            println!("Recording, press F10 again to stop");
+                            recording = Some((Recorder::new(ui.palette(), scale as usize), new_path(game_location, "rec")));
+                        }
+                    }
+                }
+                Event::KeyDown { keycode: Some(Keycode::Backspace), .. } => rewinding = true,
+                Event::KeyUp { keycode: Some(Keycode::Backspace), .. } => rewinding = false,
+                Event::KeyDown { keycode, scancode, .. } => ui.set_key_pressed(&mut vm, keycode, scancode),
+                Event::KeyUp { keycode, scancode, .. } => ui.set_key_released(&mut vm, keycode, scancode),
+                _ => controllers.handle(&event, &mut vm),
+            }
+        }
+        clock.wait();
+
+        // Snapshot once per frame, or step back one frame while rewinding
+        if rewinding {
+            if rewind.pop(&mut vm) {
+                halted = false;
+                ui.stop_sound();
+                ui.draw(&vm);
+            }
+            continue;
+        }
+        if halted {
+            continue;
+        }
+        rewind.push(&vm);
+
+        let result = match debugger.as_mut() {
+            Some(debugger) => {
+                if debugger.is_paused() {
+                    ui.stop_sound();
+                    if prompt(debugger, &mut vm) == Action::Quit {
+                        break 'running;
+                    }
+                    // don't make up for the time spent at the prompt
+                    clock = FrameClock::default();
+                }
+                debugger.run_frame_with(&mut vm, cycles_per_frame, &mut ui, &mut trace)
+            }
+            None => vm.run_frame_with(cycles_per_frame, &mut ui, &mut trace),
+        };
+        match result {
+            Ok(CycleOutcome::Exit) => break 'running,
+            Ok(_) => {}
+            Err(error) => {
+                // Keep the window open so the last frame can be inspected,
+                // or drop into the debugger prompt
+                eprintln!("Fault at PC 0x{:03X} (opcode 0x{:04X}): {}", vm.pc, vm.opcode, error);
+                ui.stop_sound();
+                halted = debugger.is_none();
             }
         }
-        ::std::thread::sleep(Duration::new(0, 1_000_000_000u32 / 600));
-        vm.emulate_cycle(&mut ui);
-        if vm.draw_flag {
-            ui.draw_canvas(&mut vm, SCALE);
+
+        // Present once per frame
+        if vm.draw_flag || ui.is_fading() {
+            ui.draw(&vm);
+            vm.draw_flag = false;
+        }
+        frames += 1;
+        if options.screenshot_after == Some(frames) {
+            save_screenshot(&vm, game_location, &ui.palette(), screenshot_scale);
         }
+        if recording.as_mut().is_some_and(|(recorder, _)| !recorder.capture(&vm)) {
+            println!("Recording is full");
+            if let Some((recorder, path)) = recording.take() {
+                save_recording(&recorder, &path);
+            }
+        }
+    }
+
+    finish_trace(tracer, &options);
+    if let Some((recorder, path)) = recording {
+        save_recording(&recorder, &path);
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,198 @@
+use std::env;
+use std::path::PathBuf;
+use std::process;
+
+use chip8_core::clock::FRAME_RATE;
+use chip8_core::filter::FILTER_MODES;
+use chip8_core::quirks::PROFILES;
+use chip8_core::rewind::DEFAULT_REWIND_DEPTH;
+use chip8_core::trace::TraceFilter;
+use chip8_core::{Palette, Quirks};
+
+use crate::config::{self, Settings};
+
+/// Window scale used unless `--scale` is given
+pub const DEFAULT_SCALE: u32 = 16;
+/// Largest accepted window scale
+const MAX_SCALE: u32 = 64;
+/// Volume in percent used unless `--volume` is given
+pub const DEFAULT_VOLUME: u32 = 25;
+/// Number of frames of a `--headless` run unless `--frames` is given
+pub const DEFAULT_HEADLESS_FRAMES: u64 = 600;
+
+const USAGE: &str = "usage: chip-8 [options] <path-to-ROM>
+
+display:
+  --scale <n>              window scale, 1 to 64 (default 16)
+  --fullscreen             run in a fullscreen window
+  --palette <theme>        classic, green, amber, lcd or octo, or colours as
+                           'RRGGBB,RRGGBB[,RRGGBB,RRGGBB]', background first
+  --filter <mode>          anti-flicker filter: off, decay or two-frame (default off)
+  --decay <percent>        intensity pixels keep per frame with the decay filter (default 50)
+
+speed and behaviour:
+  --ips <n>                instructions per second (default 600)
+  --cycles-per-frame <n>   instructions per 60 Hz frame (default 10)
+  --quirks <profile>       vip, schip, xochip or modern (default modern)
+  --seed <n>               seed of the random number generator
+
+sound:
+  --volume <percent>       volume, 0 to 100 (default 25)
+  --mute                   don't open an audio device
+
+tools:
+  --rewind <frames>        frames kept for rewinding, 0 turns it off (default 600)
+  --trace <file>           log every executed instruction
+  --trace-pc <from-to>     only log the instructions at these hex addresses
+  --trace-ops <classes>    only log these opcode classes, e.g. 'D,F' for DXYN and FXNN
+  --trace-screen           add a hash of the screen to every logged instruction
+  --record <file>          record the game as animated PNG from the start (F10 toggles)
+  --screenshot-after <n>   save a PNG screenshot after n frames (F9 takes one)
+  --screenshot-scale <n>   scale of screenshots, 1 to 64 (default: window scale)
+  --debug                  start paused in the step debugger
+  --headless               run without a window, then print the screen
+  --frames <n>             frames of a headless run (default 600)
+  --config <file>          configuration file (default ~/.config/chip-8/config.toml)
+  -h, --help               show this help";
+
+/// Settings of a run, read from the command line
+pub struct Options {
+    pub game_location: String,
+    /// Settings overriding the configuration file
+    pub settings: Settings,
+    pub fullscreen: bool,
+    pub seed: Option<u64>,
+    pub mute: bool,
+    pub rewind_depth: usize,
+    pub trace: Option<String>,
+    pub trace_filter: TraceFilter,
+    pub trace_screen: bool,
+    pub record: Option<String>,
+    pub screenshot_after: Option<u64>,
+    pub screenshot_scale: Option<u32>,
+    pub debug: bool,
+    pub headless: bool,
+    pub frames: u64,
+    pub config: Option<PathBuf>,
+}
+
+/// Prints the error and the usage and exits
+fn usage(error: &str) -> ! {
+    eprintln!("chip-8: {}\n{}", error, USAGE);
+    process::exit(2);
+}
+
+/// Parses `value` of `option` as a number from `min` to `max`
+fn number(option: &str, value: &str, min: u64, max: u64) -> u64 {
+    match value.parse::<u64>() {
+        Ok(number) if number >= min && number <= max => number,
+        _ => usage(&format!("invalid value '{}' for {}, expected a number from {} to {}", value, option, min, max)),
+    }
+}
+
+impl Options {
+    /// Reads the options from the command line, exiting on bad arguments
+    pub fn parse() -> Options {
+        let mut game_location = None;
+        let mut settings = Settings::default();
+        let mut fullscreen = false;
+        let mut seed = None;
+        let mut mute = false;
+        let mut rewind_depth = DEFAULT_REWIND_DEPTH;
+        let mut trace = None;
+        let mut trace_filter = TraceFilter::default();
+        let mut trace_screen = false;
+        let mut record = None;
+        let mut screenshot_after = None;
+        let mut screenshot_scale = None;
+        let mut debug = false;
+        let mut headless = false;
+        let mut frames = DEFAULT_HEADLESS_FRAMES;
+        let mut config = None;
+
+        let mut args = env::args().skip(1);
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().unwrap_or_else(|| usage(&format!("{} needs a value", arg)));
+
+            match arg.as_str() {
+                "--scale" => settings.scale = Some(number(&arg, &value(), 1, MAX_SCALE as u64) as u32),
+                "--fullscreen" => fullscreen = true,
+                "--palette" => {
+                    let palette = Palette::parse(&value()).unwrap_or_else(|why| usage(&format!("invalid palette: {}", why)));
+                    settings.palette = Some(palette);
+                }
+                "--filter" => {
+                    let name = value();
+                    let filter = config::filter_name(&name).unwrap_or_else(|| {
+                        usage(&format!("unknown filter '{}', expected one of {}", name, FILTER_MODES.join(", ")))
+                    });
+                    settings.filter = Some(filter);
+                }
+                "--decay" => settings.decay = Some(number(&arg, &value(), 0, 100) as u32),
+                "--ips" => {
+                    let ips = number(&arg, &value(), FRAME_RATE as u64, 1_000_000);
+                    settings.cycles_per_frame = Some(((ips + FRAME_RATE as u64 / 2) / FRAME_RATE as u64) as usize);
+                }
+                "--cycles-per-frame" => settings.cycles_per_frame = Some(number(&arg, &value(), 1, 100_000) as usize),
+                "--quirks" => {
+                    let name = value();
+                    let quirks = Quirks::from_name(&name).unwrap_or_else(|| {
+                        usage(&format!("unknown quirks profile '{}', expected one of {}", name, PROFILES.join(", ")))
+                    });
+                    settings.quirks = Some(quirks);
+                }
+                "--seed" => seed = Some(number(&arg, &value(), 0, u64::MAX)),
+                "--volume" => settings.volume = Some(number(&arg, &value(), 0, 100) as u32),
+                "--mute" => mute = true,
+                "--rewind" => rewind_depth = number(&arg, &value(), 0, 100_000) as usize,
+                "--trace" => trace = Some(value()),
+                "--trace-pc" => {
+                    let pcs = TraceFilter::parse_pcs(&value()).unwrap_or_else(|why| usage(&format!("--trace-pc: {}", why)));
+                    trace_filter.pcs = Some(pcs);
+                }
+                "--trace-screen" => trace_screen = true,
+                "--trace-ops" => {
+                    trace_filter.classes = TraceFilter::parse_classes(&value())
+                        .unwrap_or_else(|why| usage(&format!("--trace-ops: {}", why)));
+                }
+                "--record" => record = Some(value()),
+                "--screenshot-after" => screenshot_after = Some(number(&arg, &value(), 1, u64::MAX)),
+                "--screenshot-scale" => screenshot_scale = Some(number(&arg, &value(), 1, MAX_SCALE as u64) as u32),
+                "--debug" => debug = true,
+                "--headless" => headless = true,
+                "--frames" => frames = number(&arg, &value(), 1, u64::MAX),
+                "--config" => config = Some(PathBuf::from(value())),
+                "-h" | "--help" => {
+                    println!("{}", USAGE);
+                    process::exit(0);
+                }
+                _ if arg.starts_with('-') => usage(&format!("unknown option '{}'", arg)),
+                _ if game_location.is_none() => game_location = Some(arg),
+                _ => usage(&format!("unexpected argument '{}'", arg)),
+            }
+        }
+
+        if debug && headless {
+            usage("--debug needs a window, it can't be combined with --headless");
+        }
+
+        Options {
+            game_location: game_location.unwrap_or_else(|| usage("missing ROM")),
+            settings,
+            fullscreen,
+            seed,
+            mute,
+            rewind_depth,
+            trace,
+            trace_filter,
+            trace_screen,
+            record,
+            screenshot_after,
+            screenshot_scale,
+            debug,
+            headless,
+            frames,
+            config,
+        }
+    }
+}
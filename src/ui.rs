@@ -1,141 +1,186 @@
 use sdl2::audio::{AudioCallback, AudioDevice, AudioSpecDesired};
-use sdl2::keyboard::Keycode;
-use sdl2::pixels::Color;
+use sdl2::keyboard::{Keycode, Scancode};
+use sdl2::pixels::{Color, PixelFormatEnum};
 use sdl2::rect::Rect;
-use sdl2::render::Canvas;
+use sdl2::render::{Canvas, Texture};
 use sdl2::Sdl;
 use sdl2::video::Window;
 
-use crate::vm::Vm;
+use chip8_core::vm::{HIRES_SCREEN_HEIGHT, HIRES_SCREEN_WIDTH, SCREEN_HEIGHT, SCREEN_WIDTH};
+use chip8_core::audio::{Sound, Tone};
+use chip8_core::palette::Rgb;
+use chip8_core::{Filter, Frontend, Palette, Vm};
+
+use crate::keymap::KeyMap;
+
+/// Bytes per pixel of the screen texture
+const BYTES_PER_PIXEL: usize = 3;
 
 pub struct Ui {
     pub canvas: Canvas<Window>,
-    device: AudioDevice<SquareWave>
+    /// Streaming texture the screen is uploaded to, sized for the high
+    /// resolution mode; only the top left corner is used in low resolution
+    screen: Texture,
+    filter: Filter,
+    keys: KeyMap,
+    /// `None` when muted
+    device: Option<AudioDevice<Speaker>>,
+    palette: Palette
 }
 
-struct SquareWave {
-    phase_inc: f32,
-    phase: f32,
-    volume: f32,
+/// Audio callback playing the machine's `Sound`
+struct Speaker {
+    tone: Tone,
 }
 
-impl AudioCallback for SquareWave {
+impl AudioCallback for Speaker {
     type Channel = f32;
 
     fn callback(&mut self, out: &mut [f32]) {
-        // Generate a square wave
-        for x in out.iter_mut() {
-            *x = if self.phase <= 0.5 { self.volume } else { -self.volume };
-            self.phase = (self.phase + self.phase_inc) % 1.0;
-        }
+        self.tone.fill(out);
     }
 }
 
 impl Ui {
     /// Creates a new `Ui` instance with default state
-    pub fn init(sdl_context: &Sdl, scale: u32) -> Ui {
+    ///
+    /// `volume` goes from 0 to 1, no audio device is opened when it is `None`.
+    pub fn init(sdl_context: &Sdl, scale: u32, palette: Palette, filter: Filter, volume: Option<f32>,
+                fullscreen: bool, keys: KeyMap) -> Ui {
         let video_subsystem = sdl_context.video().unwrap();
-        let window = video_subsystem.window("chip-8", 64 * scale, 32 * scale)
-            .position_centered()
-            .opengl()
-            .build()
-            .unwrap();
-        let mut canvas = window.into_canvas().build().unwrap();
+        let (width, height) = (SCREEN_WIDTH as u32 * scale, SCREEN_HEIGHT as u32 * scale);
+        let mut window = video_subsystem.window("chip-8", width, height);
+        window.position_centered().opengl();
+        if fullscreen {
+            window.fullscreen_desktop();
+        }
+        let mut canvas = window.build().unwrap().into_canvas().build().unwrap();
+        // letterbox the screen into fullscreen windows
+        canvas.set_logical_size(width, height).unwrap();
 
-        canvas.set_draw_color(Color::RGB(0, 0, 0));
+        canvas.set_draw_color(to_color(palette.color(0)));
         canvas.clear();
         canvas.present();
 
+        let screen = canvas.texture_creator()
+            .create_texture_streaming(PixelFormatEnum::RGB24, HIRES_SCREEN_WIDTH as u32, HIRES_SCREEN_HEIGHT as u32)
+            .unwrap();
 
-        let audio_subsystem = sdl_context.audio().unwrap();
-        let desired_spec = AudioSpecDesired {
-            freq: Some(44000),
-            channels: Some(1),      // mono
-            samples: None,          // default sample size
-        };
-
-        let device = audio_subsystem.open_playback(None, &desired_spec, |spec| {
-            // Initialize the audio callback
-            SquareWave {
-                phase_inc: 440.0 / spec.freq as f32,
-                phase: 0.0,
-                volume: 0.25,
-            }
-        }).unwrap();
+        let device = volume.map(|volume| {
+            let audio_subsystem = sdl_context.audio().unwrap();
+            let desired_spec = AudioSpecDesired {
+                freq: Some(44000),
+                channels: Some(1),      // mono
+                samples: None,          // default sample size
+            };
+
+            audio_subsystem.open_playback(None, &desired_spec, |spec| {
+                // Initialize the audio callback
+                Speaker {
+                    tone: Tone::new(spec.freq, volume),
+                }
+            }).unwrap()
+        });
 
         Ui {
             canvas,
-            device
+            screen,
+            filter,
+            keys,
+            device,
+            palette
         }
     }
 
-    pub fn set_key_pressed(&mut self, vm: &mut Vm, keycode: Keycode) {
-        match keycode {
-            Keycode::Num1 => { vm.key_states[0x1] = true; }
-            Keycode::Num2 => { vm.key_states[0x2] = true; }
-            Keycode::Num3 => { vm.key_states[0x3] = true; }
-            Keycode::Q => { vm.key_states[0x4] = true; }
-            Keycode::W => { vm.key_states[0x5] = true; }
-            Keycode::E => { vm.key_states[0x6] = true; }
-            Keycode::A => { vm.key_states[0x7] = true; }
-            Keycode::S => { vm.key_states[0x8] = true; }
-            Keycode::D => { vm.key_states[0x9] = true; }
-            Keycode::Z => { vm.key_states[0xA] = true; }
-            Keycode::X => { vm.key_states[0x0] = true; }
-            Keycode::C => { vm.key_states[0xB] = true; }
-            Keycode::Num4 => { vm.key_states[0xC] = true; }
-            Keycode::R => { vm.key_states[0xD] = true; }
-            Keycode::F => { vm.key_states[0xE] = true; }
-            Keycode::V => { vm.key_states[0xF] = true; }
-            _ => {}
+    pub fn set_key_pressed(&mut self, vm: &mut Vm, keycode: Option<Keycode>, scancode: Option<Scancode>) {
+        if let Some(key) = self.keys.lookup(keycode, scancode) {
+            vm.key_states[key as usize] = true;
         }
     }
 
-    pub fn set_key_released(&mut self, vm: &mut Vm, keycode: Keycode) {
-        match keycode {
-            Keycode::Num1 => { vm.key_states[0x1] = false; }
-            Keycode::Num2 => { vm.key_states[0x2] = false; }
-            Keycode::Num3 => { vm.key_states[0x3] = false; }
-            Keycode::Q => { vm.key_states[0x4] = false; }
-            Keycode::W => { vm.key_states[0x5] = false; }
-            Keycode::E => { vm.key_states[0x6] = false; }
-            Keycode::A => { vm.key_states[0x7] = false; }
-            Keycode::S => { vm.key_states[0x8] = false; }
-            Keycode::D => { vm.key_states[0x9] = false; }
-            Keycode::Z => { vm.key_states[0xA] = false; }
-            Keycode::X => { vm.key_states[0x0] = false; }
-            Keycode::C => { vm.key_states[0xB] = false; }
-            Keycode::Num4 => { vm.key_states[0xC] = false; }
-            Keycode::R => { vm.key_states[0xD] = false; }
-            Keycode::F => { vm.key_states[0xE] = false; }
-            Keycode::V => { vm.key_states[0xF] = false; }
-            _ => {}
+    pub fn set_key_released(&mut self, vm: &mut Vm, keycode: Option<Keycode>, scancode: Option<Scancode>) {
+        if let Some(key) = self.keys.lookup(keycode, scancode) {
+            vm.key_states[key as usize] = false;
         }
     }
 
     /// Draws the CPU's display to the canvas
-    pub fn draw_canvas(&mut self, vm: &mut Vm, scale: u32) {
-        for i in 0..64 * 32 {
-            let current_pixel = vm.screen[i];
-            let x = (i % 64) * scale as usize;
-            let y = (i / 64) * scale as usize;
-
-            self.canvas.set_draw_color(Color::RGB(0, 0, 0));
-            if current_pixel == 1 {
-                self.canvas.set_draw_color(Color::RGB(255, 255, 255));
+    ///
+    /// The screen is uploaded to the texture at one texel per pixel and
+    /// stretched over the whole window by the renderer, so the window
+    /// keeps its size in either display mode.
+    pub fn draw_canvas(&mut self, vm: &Vm) {
+        let width = vm.screen_width();
+        let area = Rect::new(0, 0, width as u32, vm.screen_height() as u32);
+        let colors = self.filter.render(vm, &self.palette);
+
+        self.screen.with_lock(area, |buffer, pitch| {
+            for (row, colors) in colors.chunks(width).enumerate() {
+                let line = &mut buffer[row * pitch..row * pitch + width * BYTES_PER_PIXEL];
+                for (texel, color) in line.chunks_mut(BYTES_PER_PIXEL).zip(colors) {
+                    texel.copy_from_slice(&[color.0, color.1, color.2]);
+                }
             }
-            let _ = self.canvas.fill_rect(Rect::new(x as i32, y as i32, scale, scale));
-        }
+        }).unwrap();
+
+        // the letterbox bars keep whatever the back buffer held unless cleared
+        self.canvas.set_draw_color(to_color(self.palette.color(0)));
+        self.canvas.clear();
+        let _ = self.canvas.copy(&self.screen, area, None);
         self.canvas.present();
     }
 
-    /// Plays a square wave sound
-    pub fn play_sound(&mut self) {
-        self.device.resume();
+    /// Colours the screen is drawn with
+    pub fn palette(&self) -> Palette {
+        self.palette
+    }
+
+    /// Whether the display filter needs the screen to be drawn again
+    /// even though the machine didn't change it
+    pub fn is_fading(&self) -> bool {
+        !self.filter.is_settled()
+    }
+
+    /// Switches to the built-in theme after the current palette and
+    /// redraws the screen with it
+    pub fn next_theme(&mut self, vm: &Vm) {
+        let (name, palette) = self.palette.next_theme();
+        self.palette = palette;
+        let _ = self.canvas.window_mut().set_title(&format!("chip-8 - {}", name));
+        self.draw_canvas(vm);
+    }
+
+    /// Plays the sound, the XO-CHIP pattern if one was loaded
+    /// or a square wave otherwise
+    pub fn play_sound(&mut self, sound: &Sound) {
+        if let Some(device) = self.device.as_mut() {
+            device.lock().tone.set_sound(*sound);
+            device.resume();
+        }
     }
 
     /// Stops the sound
     pub fn stop_sound(&mut self) {
-        self.device.pause();
+        if let Some(device) = self.device.as_ref() {
+            device.pause();
+        }
     }
-}
\ No newline at end of file
+}
+
+fn to_color(rgb: Rgb) -> Color {
+    Color::RGB(rgb.0, rgb.1, rgb.2)
+}
+
+impl Frontend for Ui {
+    fn play_sound(&mut self, sound: &Sound) {
+        Ui::play_sound(self, sound);
+    }
+
+    fn stop_sound(&mut self) {
+        Ui::stop_sound(self);
+    }
+
+    fn draw(&mut self, vm: &Vm) {
+        self.draw_canvas(vm);
+    }
+}